@@ -1,96 +1,445 @@
 /*
- * Wrong implementation.
- *
  * Still a singly-linked list, but this time a queue.
  * Change semantics to append to head.next and keep a tail pointer.
+ *
+ * The first version of this file kept the tail as `Option<&'a mut Node<T>>`:
+ * storing a reference to yourself inside yourself, so after one `push` the
+ * list stayed mutably borrowed forever. The obvious fix -- an owning
+ * `Box<Node<T>>` chain plus a raw `*mut Node<T>` tail -- compiles, but Miri
+ * (see below) rejects it: every time `push` goes through the Box it asserts
+ * unique ownership, which invalidates the raw tail pointer taken earlier
+ * (Stacked Borrows).
+ *
+ * Rule of thumb: once you start using raw pointers, *only* use raw pointers.
+ * All nodes are allocated with `Box::new`, immediately turned into raw
+ * pointers with `Box::into_raw`, and only turned back into a Box with
+ * `Box::from_raw` when they are freed. References (`&`/`&mut`) are only
+ * handed out for the elements, never kept around internally.
  */
 
+use std::marker::PhantomData;
 use std::mem;
+use std::ptr;
 
-pub struct List<'a, T> {
+pub struct List<T> {
+    // unsafe rust: https://doc.rust-lang.org/nightly/nomicon/
     head: Link<T>,
-    // non-owning pointer = reference
-    tail: Option<&'a mut Node<T>>,
+    tail: Link<T>,
+    // we logically own `T`s (drop check, variance).
+    _marker: PhantomData<T>,
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+type Link<T> = *mut Node<T>;
 
-// This won't work for some reason I need to investigate.
-// #[derive(Clone)]
 struct Node<T> {
     elem: T,
-    next: Link<T>
+    next: Link<T>,
 }
 
-// This won't work because T would need to implement the copy trait.
-// impl<T> Clone for Node<T> {
-//     fn clone(&self) -> Self {
-//         Node { elem: self.elem, next: None }
-//     }
-// }
-
 impl<T> Node<T> {
-    fn boxed(elem: T) -> Box<Self> {
-        Box::new(Node { elem: elem, next: None })
+    fn raw(elem: T) -> Link<T> {
+        Box::into_raw(Box::new(Node { elem, next: ptr::null_mut() }))
     }
 }
 
-impl<'a, T> List<'a, T> {
-    fn new() -> Self {
-        List { head: None, tail: None }
-    }
-
-    fn push(&'a mut self, elem: T) {
-        // rust sin: storing a reference to yourself inside yourself
-        let mut new_tail = Node::boxed(elem);
-        let new_tail = match self.tail.take() {
-            None => {
-                self.head = Some(new_tail);
-                self.head.as_deref_mut()
-            },
-            Some(mut old) => {
-                old.next = Some(new_tail);
-                old.next.as_deref_mut()
-            }
-        };
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: ptr::null_mut(), tail: ptr::null_mut(), _marker: PhantomData }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let new_tail = Node::raw(elem);
+        if self.tail.is_null() {
+            self.head = new_tail;
+        } else {
+            // it's totally safe to play with pointers (r/write) in general.
+            // problems arise when you try to dereference them => unsafe blocks!
+            unsafe { (*self.tail).next = new_tail; }
+        }
         self.tail = new_tail;
     }
 
-    fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|head| {
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+        unsafe {
+            // reclaim ownership of the node so it gets freed at scope end.
+            let head = Box::from_raw(self.head);
             self.head = head.next;
-            if self.head.is_none() {
-                // if we forgot this, tail could become a dangling reference
-                self.tail = None;
+            if self.head.is_null() {
+                // if we forgot this, tail would be a dangling pointer
+                self.tail = ptr::null_mut();
             }
-            head.elem
-        })
+            Some(head.elem)
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        // `as_ref` on a raw pointer: null => None, else Some(&*ptr).
+        unsafe { self.head.as_ref().map(|node| &node.elem) }
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.as_mut().map(|node| &mut node.elem) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_null()
+    }
+
+    // Moves all of `other` behind our tail, leaving `other` empty. O(1):
+    // that's what the tail pointer is for.
+    pub fn append(&mut self, other: &mut List<T>) {
+        if other.head.is_null() {
+            return;
+        }
+        if self.tail.is_null() {
+            mem::swap(self, other);
+            return;
+        }
+        unsafe { (*self.tail).next = other.head; }
+        self.tail = other.tail;
+        other.head = ptr::null_mut();
+        other.tail = ptr::null_mut();
+    }
+
+    // Moves all of `other` in front of our head, leaving `other` empty. O(1).
+    pub fn prepend_list(&mut self, other: &mut List<T>) {
+        other.append(self);
+        mem::swap(self, other);
+    }
+
+    // Splits off everything from index `at` on into a new list. Has to walk
+    // to `at`, so O(at). Panics if `at` is past the end.
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        if at == 0 {
+            return mem::take(self);
+        }
+        // find the node that becomes our new tail
+        let mut last = self.head;
+        for _ in 1..at {
+            if last.is_null() {
+                break;
+            }
+            last = unsafe { (*last).next };
+        }
+        assert!(!last.is_null(), "split_off index (is {}) out of bounds", at);
+        unsafe {
+            let head = mem::replace(&mut (*last).next, ptr::null_mut());
+            // splitting at the very end leaves the other one empty
+            let tail = if head.is_null() { ptr::null_mut() } else { self.tail };
+            self.tail = last;
+            List { head, tail, _marker: PhantomData }
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
     }
 }
 
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+}
+
+// Iterators just walk the raw pointers; the lifetime ties the references
+// they produce to the borrow of the list.
+pub struct Iter<'a, T> {
+    next: Link<T>,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    next: Link<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head, _marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head, _marker: PhantomData }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            self.next.as_ref().map(|node| {
+                self.next = node.next;
+                &node.elem
+            })
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            self.next.as_mut().map(|node| {
+                self.next = node.next;
+                &mut node.elem
+            })
+        }
+    }
+}
+
+// $ rustup +nightly-2022-01-21 component add miri
+// $ cargo +nightly-2022-01-21 miri test
+// https://github.com/rust-lang/miri
+// An experimental interpreter for Rust's mid-level intermediate representation
+// (MIR). It can run binaries and test suites of cargo projects and detect
+// certain classes of undefined behavior.
 #[cfg(test)]
 mod test {
     use super::List;
 
     #[test]
-    fn test_lol_this_works_pop() {
-        let mut list = List::<i32>::new();
+    fn test_super_basic() {
+        let mut list = List::new();
+        list.push(1);
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(3));
         assert_eq!(list.pop(), None);
     }
 
     #[test]
-    fn test_lol_this_works_push() {
+    fn test_basics() {
         let mut list = List::new();
+        assert_eq!(list.pop(), None);
+
         list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), Some(2));
+
+        list.push(4);
+        list.push(5);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(4));
+
+        // exhaustion: tail must be reset, else the next push writes through
+        // a dangling pointer.
+        assert_eq!(list.pop(), Some(5));
+        assert_eq!(list.pop(), None);
+        assert!(list.is_empty());
+
+        list.push(6);
+        list.push(7);
+        assert_eq!(list.pop(), Some(6));
+        assert_eq!(list.pop(), Some(7));
+        assert_eq!(list.pop(), None);
     }
 
     #[test]
-    fn test_fails_if_uncommented() {
+    fn test_peek() {
         let mut list = List::new();
-        // Using the list twice fails due to self-reference in push/pop:
-        // "cannot borrow `list` as mutable more than once at a time"
+        assert_eq!(list.peek(), None);
+        assert_eq!(list.peek_mut(), None);
         list.push(1);
-        // assert_eq!(list.pop(), None);
+        list.push(2);
+        assert_eq!(list.peek(), Some(&1));
+        if let Some(v) = list.peek_mut() {
+            *v *= 10;
+        }
+        assert_eq!(list.peek(), Some(&10));
+        assert_eq!(list.pop(), Some(10));
+        assert_eq!(list.peek(), Some(&2));
     }
-}
 
+    #[test]
+    fn test_into_iter() {
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        for v in list.iter_mut() {
+            *v += 1;
+        }
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next(), Some(&mut 2));
+        assert_eq!(iter.next(), Some(&mut 3));
+        assert_eq!(iter.next(), Some(&mut 4));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_miri_food() {
+        // interleave every kind of access; under Stacked Borrows any
+        // reference kept across a push/pop would be flagged here.
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert!(list.pop() == Some(1));
+        list.push(4);
+        assert!(list.pop() == Some(2));
+        list.push(5);
+
+        assert!(list.peek() == Some(&3));
+        list.push(6);
+        if let Some(x) = list.peek_mut() {
+            *x *= 10;
+        }
+        assert!(list.peek() == Some(&30));
+        assert!(list.pop() == Some(30));
+
+        for elem in list.iter_mut() {
+            *elem *= 100;
+        }
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&400));
+        assert_eq!(iter.next(), Some(&500));
+        assert_eq!(iter.next(), Some(&600));
+        assert_eq!(iter.next(), None);
+
+        assert!(list.pop() == Some(400));
+        if let Some(x) = list.peek_mut() {
+            *x *= 10;
+        }
+        assert!(list.peek() == Some(&5000));
+        list.push(7);
+        // drop it on the ground and let the dtor exercise itself
+    }
+
+    #[test]
+    fn test_drop_long() {
+        let mut list = List::new();
+        for i in 0..100_000 {
+            list.push(i);
+        }
+    }
+
+    fn from_slice(v: &[i32]) -> List<i32> {
+        let mut list = List::new();
+        for &x in v {
+            list.push(x);
+        }
+        list
+    }
+
+    fn drain(mut list: List<i32>) -> Vec<i32> {
+        // pushing after draining checks the tail pointer is still sane
+        list.push(-1);
+        let mut v: Vec<_> = list.into_iter().collect();
+        assert_eq!(v.pop(), Some(-1));
+        v
+    }
+
+    #[test]
+    fn test_append() {
+        for (a, b) in [(&[][..], &[][..]), (&[1, 2], &[]), (&[], &[3, 4]), (&[1, 2], &[3, 4])] {
+            let mut left = from_slice(a);
+            let mut right = from_slice(b);
+            left.append(&mut right);
+            assert!(right.is_empty());
+            assert_eq!(drain(right), vec![]);
+            assert_eq!(drain(left), [a, b].concat());
+        }
+    }
+
+    #[test]
+    fn test_prepend_list() {
+        for (a, b) in [(&[][..], &[][..]), (&[1, 2], &[]), (&[], &[3, 4]), (&[1, 2], &[3, 4])] {
+            let mut list = from_slice(b);
+            let mut front = from_slice(a);
+            list.prepend_list(&mut front);
+            assert!(front.is_empty());
+            assert_eq!(drain(front), vec![]);
+            assert_eq!(drain(list), [a, b].concat());
+        }
+    }
+
+    #[test]
+    fn test_split_off() {
+        for at in 0..=4 {
+            let mut list = from_slice(&[0, 1, 2, 3]);
+            let back = list.split_off(at);
+            assert_eq!(drain(list), (0..at as i32).collect::<Vec<_>>());
+            assert_eq!(drain(back), (at as i32..4).collect::<Vec<_>>());
+        }
+        let mut empty = List::<i32>::new();
+        assert!(empty.split_off(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_oob() {
+        from_slice(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn test_append_split_roundtrip() {
+        let mut list = from_slice(&[1, 2, 3]);
+        let mut back = list.split_off(1);
+        back.push(4);
+        list.push(10);
+        list.append(&mut back);
+        back.push(5);
+        let mut front = from_slice(&[-1, 0]);
+        list.prepend_list(&mut front);
+        if let Some(x) = list.peek_mut() {
+            *x = -10;
+        }
+        assert_eq!(drain(list), vec![-10, 0, 1, 10, 2, 3, 4]);
+        assert_eq!(drain(back), vec![5]);
+    }
+}
@@ -0,0 +1,318 @@
+/*
+ * `fifth::List` for many threads at once: the Michael-Scott lock-free queue.
+ *
+ * Same head/tail node chain as `fifth`, but both ends are `AtomicPtr`s and
+ * the queue always contains one extra *sentinel* (dummy) node: `head` points
+ * at the sentinel and the first real element lives in `head.next`. That way
+ * head and tail never have to be updated together -- an empty queue is just
+ * `head == tail` with `next == null` -- and producers (working on `tail`)
+ * and consumers (working on `head`) don't fight over the same pointer.
+ *
+ * Popping moves the element out of `head.next`, which then becomes the new
+ * sentinel; the old sentinel is retired to the hazard pointer domain.
+ *
+ * `tail` is allowed to lag one node behind (push links the node first and
+ * swings `tail` second); whoever notices a lagging tail helps swing it, so
+ * a thread stalled in between can't block anybody.
+ *
+ * M. M. Michael, M. L. Scott, "Simple, Fast, and Practical Non-Blocking and
+ * Blocking Concurrent Queue Algorithms" (1996).
+ */
+
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::hazard::Domain;
+
+pub struct List<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    hazards: Domain<Node<T>>,
+    _marker: PhantomData<T>,
+}
+
+struct Node<T> {
+    // uninit in the sentinel (the very first one, or moved out by `pop`)
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn raw(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node { elem, next: AtomicPtr::new(ptr::null_mut()) }))
+    }
+}
+
+// Same reasoning as second_sync: elements travel between threads, `&T` never
+// does.
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Send> Sync for List<T> {}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        let sentinel = Node::raw(MaybeUninit::uninit());
+        List {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            hazards: Domain::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Node::raw(MaybeUninit::new(elem));
+        let hazard = self.hazards.hazard();
+        loop {
+            let tail = hazard.protect(&self.tail);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if next.is_null() {
+                // link after the last node...
+                let linked = unsafe {
+                    (*tail).next.compare_exchange(
+                        ptr::null_mut(), node, Ordering::SeqCst, Ordering::Relaxed,
+                    )
+                };
+                if linked.is_ok() {
+                    // ...then try to swing tail. Failing is fine: somebody
+                    // helped us already.
+                    let _ = self.tail.compare_exchange(
+                        tail, node, Ordering::SeqCst, Ordering::Relaxed,
+                    );
+                    return;
+                }
+            } else {
+                // tail is lagging behind, help it along and retry
+                let _ = self.tail.compare_exchange(tail, next, Ordering::SeqCst, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        // one for the sentinel, one for the node holding the element
+        let head_hazard = self.hazards.hazard();
+        let next_hazard = self.hazards.hazard();
+        loop {
+            let head = head_hazard.protect(&self.head);
+            let next = next_hazard.protect(unsafe { &(*head).next });
+            // head still current => it wasn't retired, so `next` was still
+            // reachable when we protected it.
+            if self.head.load(Ordering::SeqCst) != head {
+                continue;
+            }
+            if next.is_null() {
+                return None;
+            }
+            let tail = self.tail.load(Ordering::SeqCst);
+            if head == tail {
+                // never move head past tail: tail would point at a retired
+                // node. Help the lagging push first.
+                let _ = self.tail.compare_exchange(tail, next, Ordering::SeqCst, Ordering::Relaxed);
+                continue;
+            }
+            if self.head
+                .compare_exchange(head, next, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                // `next` is the new sentinel, its element is ours to take.
+                // Still protected, so a racing pop can't free it meanwhile.
+                let elem = unsafe { ptr::read((*next).elem.as_ptr()) };
+                head_hazard.reset();
+                next_hazard.reset();
+                unsafe { self.hazards.retire(head) };
+                return Some(elem);
+            }
+        }
+    }
+
+    // A copy of the front element, not `&T` like `fifth::List::peek`: a
+    // concurrent `pop` moves the element out and its caller may drop it while
+    // we look. See `second_sync::List::peek_copied`.
+    pub fn peek_copied(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        let head_hazard = self.hazards.hazard();
+        let next_hazard = self.hazards.hazard();
+        loop {
+            let head = head_hazard.protect(&self.head);
+            let next = next_hazard.protect(unsafe { &(*head).next });
+            if self.head.load(Ordering::SeqCst) != head {
+                continue;
+            }
+            return unsafe { next.as_ref().map(|node| node.elem.assume_init()) };
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let hazard = self.hazards.hazard();
+        let head = hazard.protect(&self.head);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let sentinel = unsafe { Box::from_raw(*self.head.get_mut()) };
+        let mut cur = sentinel.next.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            cur = node.next.load(Ordering::Relaxed);
+            unsafe { node.elem.assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert_eq!(list.pop(), None);
+        assert_eq!(list.peek_copied(), None);
+        assert!(list.is_empty());
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.peek_copied(), Some(1));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), Some(2));
+
+        list.push(4);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), None);
+        assert!(list.is_empty());
+
+        list.push(5);
+        assert_eq!(list.pop(), Some(5));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<List<i32>>();
+        is_send_sync::<List<std::cell::Cell<i32>>>();
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let list = List::new();
+        for _ in 0..200 {
+            list.push(Counted);
+        }
+        for _ in 0..150 {
+            drop(list.pop());
+        }
+        assert_eq!(DROPS.load(Ordering::Relaxed), 150);
+        drop(list);
+        // sentinels and retired nodes hold no element => no extra drops
+        assert_eq!(DROPS.load(Ordering::Relaxed), 200);
+    }
+
+    #[test]
+    fn stress_mpmc() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 5_000;
+        let list = List::new();
+        let received = AtomicUsize::new(0);
+
+        let popped: Vec<Vec<(usize, usize)>> = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let list = &list;
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        list.push((p, i));
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    let (list, received) = (&list, &received);
+                    s.spawn(move || {
+                        let mut mine = vec![];
+                        while received.load(Ordering::Relaxed) < PRODUCERS * PER_PRODUCER {
+                            if let Some(x) = list.pop() {
+                                received.fetch_add(1, Ordering::Relaxed);
+                                mine.push(x);
+                            }
+                        }
+                        mine
+                    })
+                })
+                .collect();
+            consumers.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // FIFO per producer: every consumer sees a producer's items in the
+        // order they were pushed.
+        for mine in &popped {
+            for p in 0..PRODUCERS {
+                let seq: Vec<_> = mine.iter().filter(|x| x.0 == p).map(|x| x.1).collect();
+                assert!(seq.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+        // nothing lost, nothing duplicated
+        let mut all: Vec<_> = popped.into_iter().flatten().collect();
+        all.sort_unstable();
+        let expected: Vec<_> = (0..PRODUCERS)
+            .flat_map(|p| (0..PER_PRODUCER).map(move |i| (p, i)))
+            .collect();
+        assert_eq!(all, expected);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn stress_interleaved() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 5_000;
+        let list = List::new();
+        let popped: Vec<Vec<usize>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let list = &list;
+                    s.spawn(move || {
+                        let mut mine = vec![];
+                        for i in 0..PER_THREAD {
+                            list.push(t * PER_THREAD + i);
+                            if i % 2 == 1 {
+                                mine.extend(list.pop());
+                                mine.extend(list.pop());
+                            }
+                            let _ = list.peek_copied();
+                        }
+                        mine
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut all: Vec<usize> = popped.into_iter().flatten().collect();
+        while let Some(x) = list.pop() {
+            all.push(x);
+        }
+        all.sort_unstable();
+        assert_eq!(all, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+}
@@ -54,19 +54,19 @@ pub struct Node {
 }
 
 impl List {
-    fn new() -> Self {
-        return List { head: Link::Empty };
+    pub fn new() -> Self {
+        List { head: Link::Empty }
     }
 
-    fn push(&mut self, value: i32) {
+    pub fn push(&mut self, value: i32) {
         let node = Node {
-            value: value,
+            value,
             next: mem::replace(&mut self.head, Link::Empty),
         };
         self.head = Link::More(Box::new(node));
     }
 
-    fn pop(&mut self) -> Option<i32> {
+    pub fn pop(&mut self) -> Option<i32> {
         match mem::replace(&mut self.head, Link::Empty) {
             Link::Empty => None,
             Link::More(node) => {
@@ -89,7 +89,7 @@ impl List {
         // result
     }
 
-    fn peek(&mut self) -> Option<i32> {
+    pub fn peek(&mut self) -> Option<i32> {
         // note the lack of semicolons (implicit return)
         match &self.head {
             Link::Empty => Option::None,
@@ -98,6 +98,12 @@ impl List {
     }
 }
 
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Drop for List {
     fn drop(&mut self) {
         let mut cur_link = mem::replace(&mut self.head, Link::Empty);
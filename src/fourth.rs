@@ -1,5 +1,13 @@
-use std::rc::Rc;
-use std::cell::{Ref, RefCell};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::cell::{Ref, RefCell, RefMut};
+
+// Only `next` links own their node. `prev` is a `Weak` back-pointer:
+// were it strong too, every pair of neighbours would be a reference cycle,
+// and any node that escaped the list's bookkeeping would keep itself (and
+// its neighbours) alive forever.
 
 pub struct List<T> {
     head: Link<T>,
@@ -7,17 +15,18 @@ pub struct List<T> {
 }
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
 
 struct Node<T> {
     elem: T,
-    prev: Link<T>,
+    prev: WeakLink<T>,
     next: Link<T>,
 }
 
 impl<T> Node<T> {
     fn new(elem: T) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Node {
-            elem: elem,
+            elem,
             prev: None,
             next: None,
         }))
@@ -37,13 +46,28 @@ impl<T> List<T> {
                 self.head = Some(new_head);
             }
             Some(old_head) => {
-                old_head.borrow_mut().prev = Some(new_head.clone());
+                old_head.borrow_mut().prev = Some(Rc::downgrade(&new_head));
                 new_head.borrow_mut().next = Some(old_head);
                 self.head = Some(new_head);
             }
         }
     }
 
+    pub fn push_back(&mut self, elem: T) {
+        let new_tail = Node::new(elem);
+        match self.tail.take() {
+            None => {
+                self.head = Some(new_tail.clone());
+                self.tail = Some(new_tail);
+            }
+            Some(old_tail) => {
+                old_tail.borrow_mut().next = Some(new_tail.clone());
+                new_tail.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
+                self.tail = Some(new_tail);
+            }
+        }
+    }
+
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
             match old_head.borrow_mut().next.take() {
@@ -61,7 +85,7 @@ impl<T> List<T> {
 
     pub fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
-            match old_tail.borrow_mut().prev.take() {
+            match old_tail.borrow_mut().prev.take().and_then(|prev| prev.upgrade()) {
                 None => {
                     self.head.take();
                 }
@@ -83,24 +107,338 @@ impl<T> List<T> {
     // }
 
     // Returning a Ref<T> keeps it in scope.
-    pub fn peek_front(&self) -> Option<Ref<T>> {
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
         self.head.as_ref().map(|node| {
             Ref::map(node.borrow(), |node| &node.elem)
         })
     }
 
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
+        self.tail.as_ref().map(|node| {
+            Ref::map(node.borrow(), |node| &node.elem)
+        })
+    }
+
+    // RefCell would happily hand these out through &self, but taking
+    // &mut self makes a clash with an outstanding `Ref` a compile error
+    // instead of a runtime panic.
+    pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.head.as_ref().map(|node| {
+            RefMut::map(node.borrow_mut(), |node| &mut node.elem)
+        })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.tail.as_ref().map(|node| {
+            RefMut::map(node.borrow_mut(), |node| &mut node.elem)
+        })
+    }
+}
+
+/*
+ * Fallible versions of the above.
+ *
+ * `pop_*` unwrap the node with `Rc::try_unwrap(...).ok().unwrap()` and the
+ * peeks go through `RefCell`, so both can panic if a node is unexpectedly
+ * shared or borrowed. Through the public API alone that can't happen (the
+ * borrow checker sees to it), but if it ever does -- a bug here, or a node
+ * leaking out some other way -- these report it instead. They check
+ * everything *before* touching a link, so on error the list is unchanged.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListError {
+    // Somebody besides the list holds a strong reference to the node.
+    NodeStillShared,
+    // The node (or a neighbour we'd have to relink) is borrowed in a
+    // conflicting way.
+    AlreadyBorrowed,
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::NodeStillShared => write!(f, "list node is still shared"),
+            ListError::AlreadyBorrowed => write!(f, "list node is already borrowed"),
+        }
+    }
+}
+
+impl Error for ListError {}
+
+impl<T> List<T> {
+    pub fn try_pop_front(&mut self) -> Result<Option<T>, ListError> {
+        let Some(head) = &self.head else {
+            return Ok(None);
+        };
+        // a lone node is also `tail`
+        let owners = if self.is_single() { 2 } else { 1 };
+        check_unlink(head, owners, head.try_borrow().ok().and_then(|h| h.next.clone()))?;
+        Ok(self.pop_front())
+    }
+
+    pub fn try_pop_back(&mut self) -> Result<Option<T>, ListError> {
+        let Some(tail) = &self.tail else {
+            return Ok(None);
+        };
+        // `tail` plus either `head` or the previous node's `next`
+        let prev = tail.try_borrow().ok().and_then(|t| t.prev.as_ref().and_then(Weak::upgrade));
+        check_unlink(tail, 2, prev)?;
+        Ok(self.pop_back())
+    }
+
+    pub fn try_peek_front(&self) -> Result<Option<Ref<'_, T>>, ListError> {
+        self.head.as_ref().map(|node| try_elem(node)).transpose()
+    }
+
+    pub fn try_peek_back(&self) -> Result<Option<Ref<'_, T>>, ListError> {
+        self.tail.as_ref().map(|node| try_elem(node)).transpose()
+    }
+
+    pub fn try_peek_front_mut(&mut self) -> Result<Option<RefMut<'_, T>>, ListError> {
+        self.head.as_ref().map(|node| try_elem_mut(node)).transpose()
+    }
+
+    pub fn try_peek_back_mut(&mut self) -> Result<Option<RefMut<'_, T>>, ListError> {
+        self.tail.as_ref().map(|node| try_elem_mut(node)).transpose()
+    }
+
+    fn is_single(&self) -> bool {
+        match (&self.head, &self.tail) {
+            (Some(head), Some(tail)) => Rc::ptr_eq(head, tail),
+            _ => false,
+        }
+    }
+}
+
+// Can `node` be unlinked (which also writes to `neighbour`) and unwrapped?
+// `owners` is the number of strong references the list itself holds.
+// (The `neighbour` passed in is a clone, which is fine: its count doesn't
+// matter, only whether it can be borrowed.)
+fn check_unlink<T>(
+    node: &Rc<RefCell<Node<T>>>,
+    owners: usize,
+    neighbour: Link<T>,
+) -> Result<(), ListError> {
+    if node.try_borrow_mut().is_err() {
+        return Err(ListError::AlreadyBorrowed);
+    }
+    if neighbour.is_some_and(|n| n.try_borrow_mut().is_err()) {
+        return Err(ListError::AlreadyBorrowed);
+    }
+    if Rc::strong_count(node) != owners {
+        return Err(ListError::NodeStillShared);
+    }
+    Ok(())
+}
+
+fn try_elem<T>(node: &RefCell<Node<T>>) -> Result<Ref<'_, T>, ListError> {
+    node.try_borrow()
+        .map(|node| Ref::map(node, |node| &node.elem))
+        .map_err(|_| ListError::AlreadyBorrowed)
+}
+
+fn try_elem_mut<T>(node: &RefCell<Node<T>>) -> Result<RefMut<'_, T>, ListError> {
+    node.try_borrow_mut()
+        .map(|node| RefMut::map(node, |node| &mut node.elem))
+        .map_err(|_| ListError::AlreadyBorrowed)
+}
+
+// Iter/IterMut won't work either, for the same reason: every `Ref` we hand
+// out would have to borrow from the previous one (the `RefCell` of the next
+// node lives inside the current node), and an iterator can't return
+// references into itself.
+// So instead of handing references out, take a closure and call it with each
+// element while we're holding the `Ref`/`RefMut` for it. Walking goes through
+// cloned `Rc`s, so no borrow outlives a single step.
+impl<T> List<T> {
+    pub fn for_each<F: FnMut(&T)>(&self, mut f: F) {
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            let node = node.borrow();
+            f(&node.elem);
+            cur = node.next.clone();
+        }
+    }
+
+    pub fn for_each_back<F: FnMut(&T)>(&self, mut f: F) {
+        let mut cur = self.tail.clone();
+        while let Some(node) = cur {
+            let node = node.borrow();
+            f(&node.elem);
+            cur = node.prev.as_ref().and_then(Weak::upgrade);
+        }
+    }
+
+    // &mut self: a `RefMut` can't be handed out while anyone peeks.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            let mut node = node.borrow_mut();
+            f(&mut node.elem);
+            cur = node.next.clone();
+        }
+    }
+
+    pub fn for_each_mut_back<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut cur = self.tail.clone();
+        while let Some(node) = cur {
+            let mut node = node.borrow_mut();
+            f(&mut node.elem);
+            cur = node.prev.as_ref().and_then(Weak::upgrade);
+        }
+    }
+}
+
+// Same bottom-up merge sort as `second::List::sort_by`, on the `next` links
+// only: during the sort the chain is treated as singly-linked (all `prev`
+// links cleared), and a final pass rebuilds `prev` and `tail`. Same panic
+// safety too: if `compare` panics, all the nodes go back on the list
+// (unsorted, but with working `prev` links).
+impl<T> List<T> {
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp)
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)))
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        self.tail.take();
+        let mut sort = Sorting {
+            rest: self.head.take(),
+            list: self,
+            runs: Vec::new(),
+            left: None,
+            right: None,
+            merged: None,
+            merged_tail: None,
+        };
+        while let Some(node) = sort.rest.take() {
+            {
+                let mut node = node.borrow_mut();
+                sort.rest = node.next.take();
+                node.prev.take();
+            }
+            // carried along in `right`
+            sort.right = Some(node);
+            let mut i = 0;
+            while let Some(run) = sort.runs.get_mut(i).and_then(Option::take) {
+                sort.left = Some(run);
+                sort.merge(&mut compare);
+                i += 1;
+            }
+            if i == sort.runs.len() {
+                sort.runs.push(None);
+            }
+            sort.runs[i] = sort.right.take();
+        }
+        for i in 0..sort.runs.len() {
+            sort.left = sort.runs[i].take();
+            sort.merge(&mut compare);
+        }
+        sort.list.head = sort.right.take();
+        // `sort`'s drop repairs the back links
+    }
+}
+
+// Everything a sort in progress holds; see `second`'s.
+struct Sorting<'a, T> {
+    list: &'a mut List<T>,
+    rest: Link<T>,
+    runs: Vec<Link<T>>,
+    left: Link<T>,
+    right: Link<T>,
+    // the merge's output so far, and its last node
+    merged: Link<T>,
+    merged_tail: Link<T>,
+}
+
+impl<T> Sorting<'_, T> {
+    // Stable merge of `left` and `right` into `right`.
+    fn merge<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: &mut F) {
+        while let (Some(l), Some(r)) = (&self.left, &self.right) {
+            let take_right = compare(&r.borrow().elem, &l.borrow().elem) == Ordering::Less;
+            let src = if take_right { &mut self.right } else { &mut self.left };
+            let node = src.take().unwrap();
+            *src = node.borrow_mut().next.take();
+            match &self.merged_tail {
+                Some(t) => t.borrow_mut().next = Some(node.clone()),
+                None => self.merged = Some(node.clone()),
+            }
+            self.merged_tail = Some(node);
+        }
+        let rest = self.left.take().or(self.right.take());
+        match self.merged_tail.take() {
+            Some(t) => t.borrow_mut().next = rest,
+            None => self.merged = rest,
+        }
+        self.right = self.merged.take();
+    }
+}
+
+impl<T> Drop for Sorting<'_, T> {
+    fn drop(&mut self) {
+        // After a panic: chain whatever is left onto the list. (After a
+        // normal sort every chain is empty.)
+        self.merged_tail.take();
+        let mut last: Link<T> = None;
+        let chains = [&mut self.merged, &mut self.left, &mut self.right, &mut self.rest];
+        for chain in chains.into_iter().chain(&mut self.runs) {
+            let Some(first) = chain.take() else {
+                continue;
+            };
+            match &last {
+                Some(last) => last.borrow_mut().next = Some(first.clone()),
+                None => self.list.head = Some(first.clone()),
+            }
+            let mut cur = Some(first);
+            while let Some(node) = cur {
+                cur = node.borrow().next.clone();
+                last = Some(node);
+            }
+        }
+
+        // repair the back links
+        let mut prev: Link<T> = None;
+        let mut cur = self.list.head.clone();
+        while let Some(node) = cur {
+            node.borrow_mut().prev = prev.as_ref().map(Rc::downgrade);
+            cur = node.borrow().next.clone();
+            prev = Some(node);
+        }
+        self.list.tail = prev;
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+// The `next` chain is the only thing keeping nodes alive, so letting go of
+// `tail` and unhooking one `next` at a time frees every node, without
+// recursing down the chain and without `pop_front`'s `try_unwrap`.
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
-        while self.pop_front().is_some() {}
+        self.tail.take();
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            cur = node.borrow_mut().next.take();
+        }
     }
 }
 
 pub struct IntoIter<T>(List<T>);
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -120,7 +458,42 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
 
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{List, ListError, Node};
+    use std::cell::{Cell, RefCell};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::{Rc, Weak};
+
+    // Bumps the shared counter when dropped.
+    struct Counted(Rc<Cell<usize>>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    fn counted_list(n: usize, drops: &Rc<Cell<usize>>) -> List<Counted> {
+        let mut list = List::new();
+        for _ in 0..n {
+            list.push_back(Counted(drops.clone()));
+        }
+        list
+    }
+
+    // A weak handle on every node, to check they all get freed.
+    fn weak_nodes<T>(list: &List<T>) -> Vec<Weak<RefCell<Node<T>>>> {
+        let mut nodes = vec![];
+        let mut cur = list.head.clone();
+        while let Some(node) = cur {
+            nodes.push(Rc::downgrade(&node));
+            cur = node.borrow().next.clone();
+        }
+        nodes
+    }
+
+    fn all_freed<T>(nodes: &[Weak<RefCell<Node<T>>>]) -> bool {
+        nodes.iter().all(|node| node.upgrade().is_none())
+    }
 
     #[test]
     fn test_iter_frontback() {
@@ -155,11 +528,29 @@ mod test {
     fn test_peek() {
         let mut list = List::new();
         assert!(list.peek_front().is_none());
+        assert!(list.peek_back().is_none());
+        assert!(list.peek_front_mut().is_none());
+        assert!(list.peek_back_mut().is_none());
         list.push_front(1);
         assert_eq!(&*list.peek_front().unwrap(), &1);
+        assert_eq!(&*list.peek_back().unwrap(), &1);
         // didn't change underlying structure
         assert_eq!(list.pop_front(), Some(1));
         assert_eq!(list.pop_front(), None);
+
+        list.push_front(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(&*list.peek_front().unwrap(), &1);
+        assert_eq!(&*list.peek_back().unwrap(), &3);
+        *list.peek_front_mut().unwrap() = 10;
+        *list.peek_back_mut().unwrap() = 30;
+        assert_eq!(&mut *list.peek_front_mut().unwrap(), &mut 10);
+        assert_eq!(&mut *list.peek_back_mut().unwrap(), &mut 30);
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(10));
+        assert_eq!(list.pop_back(), None);
     }
 
     #[test]
@@ -174,6 +565,298 @@ mod test {
         assert_eq!(list.pop_front(), Some(4));
         assert_eq!(list.pop_front(), Some(1));
         assert_eq!(list.pop_front(), None);
+
+        // back
+        assert_eq!(list.pop_back(), None);
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(2));
+        list.push_back(4);
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), None);
+
+        // FIFO: in at the back, out at the front
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_front(), Some(1));
+        list.push_back(3);
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_front(), Some(3));
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn test_for_each() {
+        let mut list = List::new();
+        list.push_front(3);
+        list.push_front(2);
+        list.push_front(1);
+        let mut seen = vec![];
+        list.for_each(|x| seen.push(*x));
+        assert_eq!(seen, vec![1, 2, 3]);
+        seen.clear();
+        list.for_each_back(|x| seen.push(*x));
+        assert_eq!(seen, vec![3, 2, 1]);
+        // the list is still there
+        assert_eq!(&*list.peek_front().unwrap(), &1);
+
+        List::<i32>::new().for_each(|_| panic!("empty list"));
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let mut list = List::new();
+        list.push_front(3);
+        list.push_front(2);
+        list.push_front(1);
+        list.for_each_mut(|x| *x *= 10);
+        let mut n = 0;
+        list.for_each_mut_back(|x| {
+            n += 1;
+            *x += n;
+        });
+        assert_eq!(list.pop_front(), Some(13));
+        assert_eq!(list.pop_front(), Some(22));
+        assert_eq!(list.pop_front(), Some(31));
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut list = List::new();
+        for x in [5, 1, 4, 2, 3, 2] {
+            list.push_back(x);
+        }
+        list.sort();
+        let mut seen = vec![];
+        list.for_each(|x| seen.push(*x));
+        assert_eq!(seen, vec![1, 2, 2, 3, 4, 5]);
+        // prev links and tail repaired
+        seen.clear();
+        list.for_each_back(|x| seen.push(*x));
+        assert_eq!(seen, vec![5, 4, 3, 2, 2, 1]);
+        assert_eq!(&*list.peek_back().unwrap(), &5);
+
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.pop_front(), Some(5));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+
+        let mut empty = List::<i32>::new();
+        empty.sort();
+        assert!(empty.peek_front().is_none());
+        assert!(empty.peek_back().is_none());
+    }
+
+    #[test]
+    fn test_no_leaks_drop() {
+        let drops = Rc::new(Cell::new(0));
+        let list = counted_list(100, &drops);
+        let nodes = weak_nodes(&list);
+        assert_eq!(nodes.len(), 100);
+        drop(list);
+        assert!(all_freed(&nodes));
+        assert_eq!(drops.get(), 100);
+
+        // long enough that a recursive drop would blow the stack
+        let mut long = List::new();
+        for i in 0..200_000 {
+            long.push_front(i);
+        }
+        drop(long);
+    }
+
+    #[test]
+    fn test_no_leaks_into_iter() {
+        let drops = Rc::new(Cell::new(0));
+        let list = counted_list(10, &drops);
+        let nodes = weak_nodes(&list);
+        let mut iter = list.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(drops.get(), 2);
+        drop(iter);
+        assert!(all_freed(&nodes));
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[test]
+    fn test_no_leaks_panic() {
+        let drops = Rc::new(Cell::new(0));
+
+        // panicking halfway through a sort: the nodes are spread over the
+        // sort's runs at that point, and all go back on the list
+        let mut list = counted_list(50, &drops);
+        let nodes = weak_nodes(&list);
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|_, _| {
+                calls += 1;
+                assert!(calls < 20, "comparator gave up");
+                std::cmp::Ordering::Equal
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 0);
+        let (mut len, mut len_back) = (0, 0);
+        list.for_each(|_| len += 1);
+        list.for_each_back(|_| len_back += 1);
+        assert_eq!((len, len_back), (50, 50));
+        drop(list);
+        assert!(all_freed(&nodes));
+        assert_eq!(drops.get(), 50);
+
+        // panicking inside a traversal leaves the list intact
+        let mut list = counted_list(50, &drops);
+        let nodes = weak_nodes(&list);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut seen = 0;
+            list.for_each_mut_back(|_| {
+                seen += 1;
+                assert!(seen < 10, "visitor gave up");
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 50);
+        let mut len = 0;
+        list.for_each(|_| len += 1);
+        assert_eq!(len, 50);
+        drop(list);
+        assert!(all_freed(&nodes));
+        assert_eq!(drops.get(), 100);
+    }
+
+    #[test]
+    fn test_try_ok() {
+        let mut list = List::new();
+        assert_eq!(list.try_pop_front(), Ok(None));
+        assert_eq!(list.try_pop_back(), Ok(None));
+        assert!(list.try_peek_front().unwrap().is_none());
+        assert!(list.try_peek_back_mut().unwrap().is_none());
+
+        for x in [1, 2, 3] {
+            list.push_back(x);
+        }
+        assert_eq!(&*list.try_peek_front().unwrap().unwrap(), &1);
+        assert_eq!(&*list.try_peek_back().unwrap().unwrap(), &3);
+        *list.try_peek_front_mut().unwrap().unwrap() = 10;
+        *list.try_peek_back_mut().unwrap().unwrap() = 30;
+        assert_eq!(list.try_pop_front(), Ok(Some(10)));
+        assert_eq!(list.try_pop_back(), Ok(Some(30)));
+        assert_eq!(list.try_pop_back(), Ok(Some(2)));
+        assert_eq!(list.try_pop_front(), Ok(None));
     }
-}
 
+    // The public API never lets these happen, so reach into the nodes.
+    #[test]
+    fn test_try_errors() {
+        let mut list = List::new();
+        for x in [1, 2, 3] {
+            list.push_back(x);
+        }
+        let head = list.head.clone().unwrap();
+        let middle = head.borrow().next.clone().unwrap();
+        let tail = list.tail.clone().unwrap();
+
+        // an extra strong reference
+        assert_eq!(list.try_pop_front(), Err(ListError::NodeStillShared));
+        assert_eq!(list.try_pop_back(), Err(ListError::NodeStillShared));
+
+        // a borrowed node
+        {
+            let _guard = head.borrow_mut();
+            assert_eq!(list.try_pop_front().unwrap_err(), ListError::AlreadyBorrowed);
+            assert_eq!(list.try_peek_front().unwrap_err(), ListError::AlreadyBorrowed);
+            assert_eq!(list.try_peek_front_mut().unwrap_err(), ListError::AlreadyBorrowed);
+            // the other end is fine
+            assert_eq!(&*list.try_peek_back().unwrap().unwrap(), &3);
+        }
+        {
+            let _guard = tail.borrow();
+            assert!(list.try_peek_back().is_ok());
+            assert_eq!(list.try_peek_back_mut().unwrap_err(), ListError::AlreadyBorrowed);
+        }
+        drop((head, tail));
+
+        // a borrowed neighbour that popping would have to relink
+        {
+            let _guard = middle.borrow();
+            assert_eq!(list.try_pop_front(), Err(ListError::AlreadyBorrowed));
+            assert_eq!(list.try_pop_back(), Err(ListError::AlreadyBorrowed));
+        }
+        drop(middle);
+
+        // nothing was changed by the failed attempts
+        let mut seen = vec![];
+        list.for_each(|x| seen.push(*x));
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(list.try_pop_front(), Ok(Some(1)));
+        assert_eq!(list.try_pop_back(), Ok(Some(3)));
+
+        // the last node is both head and tail
+        let only = list.head.clone().unwrap();
+        assert_eq!(list.try_pop_back(), Err(ListError::NodeStillShared));
+        assert_eq!(list.try_pop_front(), Err(ListError::NodeStillShared));
+        drop(only);
+        assert_eq!(list.try_pop_back(), Ok(Some(2)));
+
+        assert_eq!(ListError::NodeStillShared.to_string(), "list node is still shared");
+        assert_eq!(ListError::AlreadyBorrowed.to_string(), "list node is already borrowed");
+    }
+
+    #[test]
+    fn test_sort_stable() {
+        let mut seed = 54321u32;
+        let items: Vec<(u32, usize)> = (0..500)
+            .map(|i| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ((seed >> 16) % 7, i)
+            })
+            .collect();
+        let mut list = List::new();
+        for &x in &items {
+            list.push_back(x);
+        }
+        list.sort_by_key(|x| x.0);
+        let mut expected = items.clone();
+        expected.sort_by_key(|x| x.0);
+        let mut back = vec![];
+        list.for_each_back(|x| back.push(*x));
+        back.reverse();
+        assert_eq!(back, expected);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_sort_panic_long() {
+        // Unwinding out of a long sort mustn't drop the runs recursively.
+        let n = 200_000;
+        let mut list = List::new();
+        for i in (0..n).rev() {
+            list.push_back(i);
+        }
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                calls += 1;
+                assert!(calls < 1_000_000, "comparator gave up");
+                a.cmp(b)
+            })
+        }));
+        assert!(result.is_err());
+        list.sort();
+        let mut expected = 0;
+        list.for_each(|&x| {
+            assert_eq!(x, expected);
+            expected += 1;
+        });
+        assert_eq!(expected, n);
+        assert_eq!(*list.peek_back().unwrap(), n - 1);
+    }
+}
@@ -0,0 +1,528 @@
+/*
+ * `fourth`'s deque without `Rc` or `RefCell`: nodes live in a slab (a Vec)
+ * and link to each other by index, like `fifth_noref`.
+ *
+ * A plain index isn't enough once indices leave the list: `push_*` return a
+ * `Handle` that can later be used to look at or remove that element in O(1),
+ * but by then the element may be gone and its slot reused by another one.
+ * So every slot carries a *generation*, bumped whenever it's vacated, and a
+ * handle is (index, generation): a handle to a removed element simply stops
+ * matching instead of silently pointing at its successor. The `prev`/`next`
+ * links are handles too, which turns a stale link (a bug here) into a panic
+ * rather than a corrupted list.
+ *
+ * No runtime borrow flags: `peek`s and iterators hand out plain `&T` and
+ * `&mut T`, checked at compile time like any Vec.
+ */
+
+use std::marker::PhantomData;
+use std::mem;
+
+pub struct List<T> {
+    slots: Vec<Slot<T>>,
+    head: Link,
+    tail: Link,
+    // first vacant slot; vacant slots are chained through `Entry::Vacant`
+    free: Option<usize>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+type Link = Option<Handle>;
+
+struct Slot<T> {
+    generation: u64,
+    entry: Entry<T>,
+}
+
+enum Entry<T> {
+    Occupied(Node<T>),
+    Vacant(Option<usize>),
+}
+
+struct Node<T> {
+    elem: T,
+    prev: Link,
+    next: Link,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { slots: Vec::new(), head: None, tail: None, free: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) -> Handle {
+        let new = self.alloc(Node { elem, prev: None, next: self.head });
+        match self.head {
+            Some(old) => self.node_mut(old).prev = Some(new),
+            None => self.tail = Some(new),
+        }
+        self.head = Some(new);
+        new
+    }
+
+    pub fn push_back(&mut self, elem: T) -> Handle {
+        let new = self.alloc(Node { elem, prev: self.tail, next: None });
+        match self.tail {
+            Some(old) => self.node_mut(old).next = Some(new),
+            None => self.head = Some(new),
+        }
+        self.tail = Some(new);
+        new
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| self.unlink(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|tail| self.unlink(tail))
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        self.head.map(|head| &self.node(head).elem)
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        self.tail.map(|tail| &self.node(tail).elem)
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|head| &mut self.node_mut(head).elem)
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|tail| &mut self.node_mut(tail).elem)
+    }
+
+    // None if the element was removed in the meantime.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.lookup(handle).map(|node| &node.elem)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.lookup_mut(handle).map(|node| &mut node.elem)
+    }
+
+    // O(1) wherever the element is. None if it's gone already.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.lookup(handle)?;
+        Some(self.unlink(handle))
+    }
+
+    // O(1), and `handle` stays valid. None if the element is gone.
+    pub fn move_to_front(&mut self, handle: Handle) -> Option<&mut T> {
+        self.lookup(handle)?;
+        if self.head != Some(handle) {
+            let Node { prev, next, .. } = *self.node(handle);
+            // not the head, so there is a `prev`, and a head to go before
+            self.node_mut(prev.unwrap()).next = next;
+            match next {
+                Some(next) => self.node_mut(next).prev = prev,
+                None => self.tail = prev,
+            }
+            let old = self.head.unwrap();
+            self.node_mut(old).prev = Some(handle);
+            let node = self.node_mut(handle);
+            node.prev = None;
+            node.next = Some(old);
+            self.head = Some(handle);
+        }
+        Some(&mut self.node_mut(handle).elem)
+    }
+
+    // `handle` must be live.
+    fn unlink(&mut self, handle: Handle) -> T {
+        let node = self.dealloc(handle);
+        match node.prev {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        node.elem
+    }
+
+    fn alloc(&mut self, node: Node<T>) -> Handle {
+        self.len += 1;
+        match self.free {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                match mem::replace(&mut slot.entry, Entry::Occupied(node)) {
+                    Entry::Vacant(next_free) => self.free = next_free,
+                    Entry::Occupied(_) => unreachable!("free list points at a live node"),
+                }
+                Handle { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, entry: Entry::Occupied(node) });
+                Handle { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+
+    // `handle` must be live. Bumps the generation, so every outstanding
+    // handle to this slot goes stale.
+    fn dealloc(&mut self, handle: Handle) -> Node<T> {
+        let slot = &mut self.slots[handle.index];
+        match mem::replace(&mut slot.entry, Entry::Vacant(self.free)) {
+            Entry::Occupied(node) => {
+                slot.generation += 1;
+                self.free = Some(handle.index);
+                self.len -= 1;
+                node
+            }
+            Entry::Vacant(_) => unreachable!("link points at a vacant slot"),
+        }
+    }
+
+    fn lookup(&self, handle: Handle) -> Option<&Node<T>> {
+        match self.slots.get(handle.index) {
+            Some(Slot { generation, entry: Entry::Occupied(node) }) if *generation == handle.generation => {
+                Some(node)
+            }
+            _ => None,
+        }
+    }
+
+    fn lookup_mut(&mut self, handle: Handle) -> Option<&mut Node<T>> {
+        match self.slots.get_mut(handle.index) {
+            Some(Slot { generation, entry: Entry::Occupied(node) }) if *generation == handle.generation => {
+                Some(node)
+            }
+            _ => None,
+        }
+    }
+
+    // For our own links, which must always be live.
+    fn node(&self, handle: Handle) -> &Node<T> {
+        self.lookup(handle).expect("link points at a dead node")
+    }
+
+    fn node_mut(&mut self, handle: Handle) -> &mut Node<T> {
+        self.lookup_mut(handle).expect("link points at a dead node")
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*
+ * Iterators. `Iter` just follows the links through `&self`.
+ *
+ * `IterMut` is harder in safe Rust: we can't look up a link in the slab
+ * while holding `&mut`s to elements we already returned. The borrow checker
+ * doesn't know that a list visits each slot at most once, so `IterMut` holds
+ * a raw pointer to the slots instead and does the lookups through that. Each
+ * node is only ever turned into a `&mut` once (`len` stops the two ends
+ * before they meet), so the `&mut`s handed out never overlap.
+ */
+pub struct Iter<'a, T> {
+    list: &'a List<T>,
+    front: Link,
+    back: Link,
+    len: usize,
+}
+
+pub struct IterMut<'a, T> {
+    slots: *mut Slot<T>,
+    slot_count: usize,
+    front: Link,
+    back: Link,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { list: self, front: self.head, back: self.tail, len: self.len }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.as_mut_ptr(),
+            slot_count: self.slots.len(),
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> IterMut<'a, T> {
+    // `List::node_mut`, through the raw pointer.
+    fn node(&mut self, handle: Handle) -> &'a mut Node<T> {
+        assert!(handle.index < self.slot_count, "link points at a dead node");
+        // in bounds, and nobody else touches the slots while we borrow them
+        let slot = unsafe { &mut *self.slots.add(handle.index) };
+        match slot {
+            Slot { generation, entry: Entry::Occupied(node) } if *generation == handle.generation => node,
+            _ => panic!("link points at a dead node"),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.list.node(self.front?);
+        self.len -= 1;
+        self.front = node.next;
+        Some(&node.elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.list.node(self.back?);
+        self.len -= 1;
+        self.back = node.prev;
+        Some(&node.elem)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.node(self.front?);
+        self.len -= 1;
+        self.front = node.next;
+        Some(&mut node.elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.node(self.back?);
+        self.len -= 1;
+        self.back = node.prev;
+        Some(&mut node.elem)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// IterMut is morally a `&mut List`; the raw pointer opts it out of the auto
+// impls.
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::collections::VecDeque;
+
+    fn contents<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.peek_front(), None);
+
+        list.push_front(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek_front(), Some(&1));
+        assert_eq!(list.peek_back(), Some(&3));
+        *list.peek_front_mut().unwrap() = 10;
+        *list.peek_back_mut().unwrap() = 30;
+        assert_eq!(contents(&list), vec![10, 2, 30]);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+        assert_eq!(list.peek_back(), None);
+    }
+
+    #[test]
+    fn handles() {
+        let mut list = List::new();
+        let a = list.push_back('a');
+        let b = list.push_back('b');
+        let c = list.push_back('c');
+        assert_eq!(list.get(b), Some(&'b'));
+        *list.get_mut(a).unwrap() = 'A';
+
+        // moving an element keeps its handle
+        assert_eq!(list.move_to_front(c), Some(&mut 'c'));
+        assert_eq!(contents(&list), vec!['c', 'A', 'b']);
+        list.move_to_front(b);
+        list.move_to_front(b);
+        list.move_to_front(a);
+        assert_eq!(contents(&list), vec!['A', 'b', 'c']);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec!['c', 'b', 'A']);
+
+        assert_eq!(list.remove(b), Some('b'));
+        assert_eq!(contents(&list), vec!['A', 'c']);
+        // stale now, and stays stale when the slot is reused
+        assert_eq!(list.remove(b), None);
+        assert_eq!(list.move_to_front(b), None);
+        let d = list.push_front('d');
+        assert_eq!(d.index, b.index);
+        assert_eq!(list.get(b), None);
+        assert_eq!(list.get_mut(b), None);
+        assert_eq!(list.get(d), Some(&'d'));
+
+        assert_eq!(list.remove(c), Some('c'));
+        assert_eq!(list.peek_back(), Some(&'A'));
+        assert_eq!(list.remove(d), Some('d'));
+        assert_eq!(list.peek_front(), Some(&'A'));
+        assert_eq!(list.remove(a), Some('A'));
+        assert!(list.is_empty());
+        assert_eq!(list.peek_front(), None);
+        assert_eq!(list.peek_back(), None);
+        // the slab is reused, not grown
+        list.push_back('e');
+        assert_eq!(list.slots.len(), 3);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list = List::new();
+        let handles: Vec<_> = (0..10).map(|i| list.push_back(i)).collect();
+        for &h in handles.iter().step_by(3) {
+            list.remove(h);
+        }
+        assert_eq!(contents(&list), vec![1, 2, 4, 5, 7, 8]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![8, 7, 5, 4, 2, 1]);
+        assert_eq!(list.iter().len(), 6);
+
+        for x in &mut list {
+            *x *= 10;
+        }
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.len(), 6);
+        *iter.next_back().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        // both ends meet in the middle
+        assert_eq!(iter.by_ref().count(), 4);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(contents(&list), vec![12, 20, 40, 50, 70, 81]);
+
+        let mut into = list.into_iter();
+        assert_eq!(into.next_back(), Some(81));
+        assert_eq!(into.collect::<Vec<_>>(), vec![12, 20, 40, 50, 70]);
+    }
+
+    #[test]
+    fn against_model() {
+        let mut list = List::new();
+        // (value, handle) pairs in list order
+        let mut model = VecDeque::new();
+        let mut seed = 3u32;
+        let mut rand = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        for i in 0..5000 {
+            match rand(5) {
+                0 => model.push_front((i, list.push_front(i))),
+                1 => model.push_back((i, list.push_back(i))),
+                2 => assert_eq!(list.pop_front(), model.pop_front().map(|e| e.0)),
+                3 => assert_eq!(list.pop_back(), model.pop_back().map(|e| e.0)),
+                _ if !model.is_empty() => {
+                    let (value, handle) = model.remove(rand(model.len())).unwrap();
+                    assert_eq!(list.remove(handle), Some(value));
+                }
+                _ => {}
+            }
+            assert_eq!(list.len(), model.len());
+            assert_eq!(list.peek_front(), model.front().map(|e| &e.0));
+            assert_eq!(list.peek_back(), model.back().map(|e| &e.0));
+        }
+        assert!(list.iter().eq(model.iter().map(|e| &e.0)));
+        assert!(list.iter().rev().eq(model.iter().rev().map(|e| &e.0)));
+    }
+}
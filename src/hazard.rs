@@ -0,0 +1,210 @@
+/*
+ * Hazard pointers: safe memory reclamation for lock-free structures.
+ *
+ * In a lock-free list a thread can load a node pointer, get descheduled, and
+ * meanwhile another thread unlinks and frees that very node. Dereferencing it
+ * afterwards is a use-after-free; worse, the allocator may hand the address
+ * out again and a CAS comparing addresses would wrongly succeed (ABA).
+ *
+ * The fix: before dereferencing, a thread *publishes* the pointer in a slot
+ * everybody can see (its hazard pointer). Whoever unlinks a node doesn't
+ * free it, but *retires* it; retired nodes are only freed once no hazard
+ * pointer points at them.
+ *
+ * Maged M. Michael, "Hazard Pointers: Safe Memory Reclamation for Lock-Free
+ * Objects" (2004).
+ *
+ * Each data structure owns its own `Domain`, so the retired nodes all have
+ * the same type and `Drop` on the structure can free whatever is left.
+ */
+
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+// Scan for reclaimable nodes once this many have been retired.
+const SCAN_THRESHOLD: usize = 64;
+
+pub struct Domain<T> {
+    // append-only list of hazard slots, reused between threads
+    records: AtomicPtr<Record<T>>,
+    // lock-free stack of unlinked nodes waiting to be freed
+    retired: AtomicPtr<Retired<T>>,
+    retired_count: AtomicUsize,
+}
+
+struct Record<T> {
+    ptr: AtomicPtr<T>,
+    active: AtomicBool,
+    // never changes once the record is published
+    next: *mut Record<T>,
+}
+
+struct Retired<T> {
+    ptr: *mut T,
+    next: *mut Retired<T>,
+}
+
+// A claimed hazard slot. Released (and cleared) on drop.
+pub struct Hazard<'a, T> {
+    record: &'a Record<T>,
+}
+
+impl<T> Domain<T> {
+    pub fn new() -> Self {
+        Domain {
+            records: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn hazard(&self) -> Hazard<'_, T> {
+        // try to reuse a slot some finished thread gave back
+        let mut cur = self.records.load(Ordering::Acquire);
+        while let Some(record) = unsafe { cur.as_ref() } {
+            if !record.active.load(Ordering::Relaxed)
+                && record.active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return Hazard { record };
+            }
+            cur = record.next;
+        }
+
+        // none free: publish a new one
+        let record = Box::into_raw(Box::new(Record {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.records.load(Ordering::Acquire);
+        loop {
+            unsafe { (*record).next = head; }
+            match self.records.compare_exchange_weak(
+                head, record, Ordering::Release, Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => head = actual,
+            }
+        }
+        Hazard { record: unsafe { &*record } }
+    }
+
+    // Hands `ptr` over to the domain, which frees it (as a `Box<T>`) once no
+    // hazard protects it anymore.
+    //
+    // Safety: `ptr` must come from `Box::into_raw`, must already be
+    // unreachable for threads that haven't protected it yet, and must be
+    // retired only once.
+    pub unsafe fn retire(&self, ptr: *mut T) {
+        self.push_retired(ptr);
+        if self.retired_count.load(Ordering::Relaxed) >= SCAN_THRESHOLD {
+            self.scan();
+        }
+    }
+
+    fn push_retired(&self, ptr: *mut T) {
+        let node = Box::into_raw(Box::new(Retired { ptr, next: ptr::null_mut() }));
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next = head; }
+            match self.retired.compare_exchange_weak(
+                head, node, Ordering::Release, Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => head = actual,
+            }
+        }
+        self.retired_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn scan(&self) {
+        // take the whole retired list; other threads keep retiring into a
+        // fresh one meanwhile.
+        let mut cur = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+
+        // SeqCst pairs with `Hazard::protect`: either we see their hazard, or
+        // they see the node already unlinked and back off.
+        let mut protected = Vec::new();
+        let mut record = self.records.load(Ordering::SeqCst);
+        while let Some(r) = unsafe { record.as_ref() } {
+            let p = r.ptr.load(Ordering::SeqCst);
+            if !p.is_null() {
+                protected.push(p);
+            }
+            record = r.next;
+        }
+
+        while !cur.is_null() {
+            let retired = unsafe { Box::from_raw(cur) };
+            cur = retired.next;
+            self.retired_count.fetch_sub(1, Ordering::Relaxed);
+            if protected.contains(&retired.ptr) {
+                // still in use, try again next scan
+                self.push_retired(retired.ptr);
+            } else {
+                unsafe { drop(Box::from_raw(retired.ptr)) };
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn retired_count(&self) -> usize {
+        self.retired_count.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Default for Domain<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Domain<T> {
+    fn drop(&mut self) {
+        // &mut self: every `Hazard` (which borrows the domain) is gone, so
+        // nothing is protected anymore.
+        let mut cur = *self.retired.get_mut();
+        while !cur.is_null() {
+            let retired = unsafe { Box::from_raw(cur) };
+            cur = retired.next;
+            unsafe { drop(Box::from_raw(retired.ptr)) };
+        }
+        let mut cur = *self.records.get_mut();
+        while !cur.is_null() {
+            let record = unsafe { Box::from_raw(cur) };
+            cur = record.next;
+        }
+    }
+}
+
+impl<T> Hazard<'_, T> {
+    // Loads `src` and protects the result. Loops until the pointer is still
+    // in `src` *after* being published, so it can't have been retired (and
+    // won't be freed) before we protected it.
+    pub fn protect(&self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Ordering::Relaxed);
+        loop {
+            self.record.ptr.store(ptr, Ordering::SeqCst);
+            // SeqCst (not just Acquire) so this re-load can't be ordered
+            // before the hazard store above.
+            let again = src.load(Ordering::SeqCst);
+            if again == ptr {
+                return ptr;
+            }
+            ptr = again;
+        }
+    }
+
+    pub fn reset(&self) {
+        self.record.ptr.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl<T> Drop for Hazard<'_, T> {
+    fn drop(&mut self) {
+        self.reset();
+        self.record.active.store(false, Ordering::Release);
+    }
+}
@@ -1,7 +1,20 @@
+mod hazard;
+
 pub mod first;
 pub mod second;
+pub mod second_arena;
+pub mod second_sync;
 pub mod third;
+pub mod third_sync;
+pub mod third_queue;
+pub mod third_stream;
+pub mod third_skew;
 pub mod fourth;
+pub mod fourth_slab;
 pub mod fifth;
 pub mod fifth_noref;
-
+pub mod fifth_sync;
+pub mod sixth;
+pub mod lru;
+pub mod traits;
+pub mod unrolled;
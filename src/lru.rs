@@ -0,0 +1,329 @@
+/*
+ * Least-recently-used cache: the classic use of a doubly-linked list.
+ *
+ * The entries live in a `fourth_slab::List`, most recently used at the
+ * front; the `HashMap` maps each key to the handle of its entry. A hit moves
+ * the entry to the front, a miss that overflows the capacity drops the back
+ * -- all O(1), neither side ever walks the list.
+ *
+ * Every key is stored twice (map and list) so it can be removed from the map
+ * when its entry is evicted off the back; hence `K: Clone`.
+ *
+ * Why slab handles and not pointers into a `sixth::List`: the map only
+ * finds the right handle if `K`'s `Hash`, `Eq` and `Clone` agree with each
+ * other, and that's up to the user. With a broken key (or one that panics
+ * halfway through `put`) the map can end up holding the handle of an entry
+ * that's been evicted. A pointer would then dangle; a slab handle just stops
+ * matching, and the cache returns wrong answers but stays memory safe, the
+ * same promise `HashMap` makes.
+ */
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use crate::fourth_slab::{self, Handle};
+
+pub struct LruCache<K, V> {
+    map: HashMap<K, Handle>,
+    // most recently used first
+    order: fourth_slab::List<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    // A capacity of 0 is allowed: such a cache never holds anything.
+    pub fn new(capacity: usize) -> Self {
+        LruCache { map: HashMap::new(), order: fourth_slab::List::new(), capacity }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Marks the entry as most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.order.move_to_front(handle).map(|(_, v)| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.order.move_to_front(handle).map(|(_, v)| v)
+    }
+
+    // Like `get`, but leaves the recency order alone.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.order.get(*self.map.get(key)?).map(|(_, v)| v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(key).is_some()
+    }
+
+    // Inserts or updates the entry and marks it as most recently used.
+    // Returns the old value if the key was present; otherwise, if the cache
+    // is full, evicts the least recently used entry to make room.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            if let Some((_, old)) = self.order.move_to_front(handle) {
+                return Some(mem::replace(old, value));
+            }
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+        if self.len() == self.capacity {
+            self.pop_lru();
+        }
+        let handle = self.order.push_front((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        self.order.remove(handle).map(|(_, v)| v)
+    }
+
+    // The entry that would be evicted next, without touching it.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.order.peek_back().map(|(k, v)| (k, v))
+    }
+
+    // Evicts the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.order.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    // Shrinking evicts the least recently used entries that don't fit.
+    pub fn resize(&mut self, capacity: usize) {
+        while self.len() > capacity {
+            self.pop_lru();
+        }
+        self.capacity = capacity;
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order = fourth_slab::List::new();
+    }
+}
+
+// Most recently used first; `.rev()` for least recently used first. Doesn't
+// change the order.
+pub struct Iter<'a, K, V>(fourth_slab::Iter<'a, (K, V)>);
+
+impl<K, V> LruCache<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.order.iter())
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::LruCache;
+    use std::collections::VecDeque;
+    use std::hash::{Hash, Hasher};
+
+    fn keys(cache: &LruCache<i32, i32>) -> Vec<i32> {
+        cache.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut cache = LruCache::new(2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.put(1, 10), None);
+        assert_eq!(cache.put(2, 20), None);
+        assert_eq!(keys(&cache), vec![2, 1]);
+
+        // a hit makes 1 the most recent, so 2 is evicted next
+        assert_eq!(cache.get(&1), Some(&10));
+        assert_eq!(cache.put(3, 30), None);
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(keys(&cache), vec![3, 1]);
+
+        // updating also counts as a use
+        assert_eq!(cache.put(1, 11), Some(10));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.peek_lru(), Some((&3, &30)));
+
+        // peek doesn't
+        assert_eq!(cache.peek(&3), Some(&30));
+        cache.put(4, 40);
+        assert!(!cache.contains_key(&3));
+        assert_eq!(keys(&cache), vec![4, 1]);
+        assert_eq!(cache.iter().rev().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1, 4]);
+
+        *cache.get_mut(&1).unwrap() += 1;
+        assert_eq!(cache.remove(&1), Some(12));
+        assert_eq!(cache.remove(&1), None);
+        assert_eq!(cache.pop_lru(), Some((4, 40)));
+        assert!(cache.is_empty());
+        assert_eq!(cache.pop_lru(), None);
+    }
+
+    #[test]
+    fn resize() {
+        let mut cache = LruCache::new(5);
+        for i in 0..5 {
+            cache.put(i, i);
+        }
+        cache.get(&0);
+        cache.resize(2);
+        assert_eq!(keys(&cache), vec![0, 4]);
+        assert_eq!(cache.capacity(), 2);
+
+        cache.resize(3);
+        cache.put(5, 5);
+        assert_eq!(keys(&cache), vec![5, 0, 4]);
+
+        cache.resize(0);
+        assert!(cache.is_empty());
+        assert_eq!(cache.put(6, 6), None);
+        assert!(cache.is_empty());
+
+        cache.resize(1);
+        cache.put(7, 7);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&7), None);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut cache = LruCache::new(2);
+        cache.put(String::from("a"), 1);
+        cache.put(String::from("b"), 2);
+        assert_eq!(cache.get("a"), Some(&1));
+        cache.put(String::from("c"), 3);
+        assert!(!cache.contains_key("b"));
+        // owned values are dropped properly on eviction and at the end
+        assert_eq!(cache.pop_lru(), Some((String::from("a"), 1)));
+    }
+
+    #[test]
+    fn against_model() {
+        // a VecDeque of (key, value), most recent first
+        let mut cache = LruCache::new(8);
+        let mut model: VecDeque<(i32, i32)> = VecDeque::new();
+        let mut seed = 11u32;
+        let mut rand = |n: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            ((seed >> 16) % n) as i32
+        };
+        for i in 0..5000 {
+            let key = rand(16);
+            match rand(3) {
+                0 => {
+                    let expected = model.iter().position(|e| e.0 == key).map(|at| {
+                        let entry = model.remove(at).unwrap();
+                        model.push_front(entry);
+                        entry.1
+                    });
+                    assert_eq!(cache.get(&key).copied(), expected);
+                }
+                1 => {
+                    let expected = model.iter().position(|e| e.0 == key).map(|at| model.remove(at).unwrap().1);
+                    assert_eq!(cache.remove(&key), expected);
+                }
+                _ => {
+                    let old = model.iter().position(|e| e.0 == key).map(|at| model.remove(at).unwrap().1);
+                    if old.is_none() && model.len() == 8 {
+                        model.pop_back();
+                    }
+                    model.push_front((key, i));
+                    assert_eq!(cache.put(key, i), old);
+                }
+            }
+            assert!(cache.iter().map(|(k, v)| (*k, *v)).eq(model.iter().copied()));
+        }
+    }
+
+    #[test]
+    fn inconsistent_keys() {
+        // Each clone compares (and hashes) differently from its original, so
+        // the copy kept in the list never finds its own map entry again.
+        #[derive(Debug, PartialEq, Eq)]
+        struct Fickle(u32);
+        impl Clone for Fickle {
+            fn clone(&self) -> Self {
+                Fickle(self.0 + 1000)
+            }
+        }
+        impl Hash for Fickle {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        let mut cache = LruCache::new(2);
+        cache.put(Fickle(1), 1);
+        cache.put(Fickle(2), 2);
+        // evicts 1, but misses its map entry: the map keeps a stale handle
+        cache.put(Fickle(3), 3);
+        assert_eq!(cache.len(), 2);
+        // which must never reach the evicted (or a reused) entry
+        assert_eq!(cache.get(&Fickle(1)), None);
+        assert_eq!(cache.get_mut(&Fickle(1)), None);
+        assert_eq!(cache.peek(&Fickle(1)), None);
+        assert!(!cache.contains_key(&Fickle(1)));
+        assert_eq!(cache.remove(&Fickle(1)), None);
+        assert_eq!(cache.put(Fickle(1), 10), None);
+        assert_eq!(cache.get(&Fickle(1)), Some(&10));
+        assert_eq!(cache.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![10, 3]);
+        cache.resize(0);
+        assert!(cache.is_empty());
+    }
+}
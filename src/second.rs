@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 pub struct List<T> {
     head: Link<T>
@@ -11,34 +14,40 @@ struct Node<T> {
 }
 
 impl<T> List<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         List { head: None }
     }
 
-    fn push(&mut self, elem: T) {
+    pub fn push(&mut self, elem: T) {
         self.head = Link::Some(Box::new(Node {
-            elem: elem,
+            elem,
             next: self.head.take(),
         }))
     }
 
-    fn pop(&mut self) -> Option<T> {
+    pub fn pop(&mut self) -> Option<T> {
         self.head.take().map(|node| {
             self.head = node.next;
             node.elem
         })
     }
 
-    fn peek(&mut self) -> Option<&T> {
+    pub fn peek(&mut self) -> Option<&T> {
         // impl<T> Option<T> { pub fn as_ref(&self) -> Option<&T>; }
         self.head.as_ref().map(|node| { &node.elem })
     }
 
-    fn peek_mut(&mut self) -> Option<&mut T> {
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
         self.head.as_mut().map(|node| { &mut node.elem })
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur = self.head.take();
@@ -56,8 +65,10 @@ impl<T> Drop for List<T> {
 // Tuple: intoiter.0 == List<T>
 pub struct IntoIter<T>(List<T>);
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -130,6 +141,341 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/*
+ * Standard traits.
+ *
+ * `#[derive]` would generate recursive impls (`Node::clone` calls
+ * `Option<Box<Node>>::clone` calls `Node::clone`...), one stack frame per
+ * node, so a long enough list overflows the stack -- the same problem
+ * `Drop` has above. Everything here goes through the iterators instead.
+ */
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+// lexicographic, like slices
+impl<T: PartialOrd> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // the length keeps e.g. [[1], [2]] and [[1, 2]] apart
+        let mut len = 0;
+        for elem in self {
+            elem.hash(state);
+            len += 1;
+        }
+        state.write_usize(len);
+    }
+}
+
+// `collect` keeps the iterator's order: the first element ends up on top,
+// i.e. `iter()` gives the elements back in the order they came in.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// Appends at the *bottom* of the stack (unlike repeated `push`), so that
+// `extend` and `collect` agree on the order. Walks the list once to find
+// the end, then links every new node there.
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut link = &mut self.head;
+        while link.is_some() {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        for elem in iter {
+            link = &mut link.insert(Box::new(Node { elem, next: None })).next;
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for List<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/*
+ * Sorting: bottom-up merge sort that relinks the existing boxes, so no
+ * element is moved or reallocated. O(n log n) comparisons, O(log n) extra
+ * space for the run table.
+ *
+ * Nodes are taken off the list one at a time and "carried" through
+ * `runs` like a binary counter: `runs[i]` is either empty or a sorted run
+ * of 2^i nodes. Stability: a run at a higher index only ever holds nodes
+ * that came *earlier* in the list, and `merge` prefers its left argument on
+ * ties, so older runs always go on the left.
+ *
+ * If `compare` panics, the nodes are all put back on the list, in no
+ * particular order. (Just dropping them would drop each run as a plain
+ * `Box` chain, recursively: the stack overflow our `Drop` is there to avoid.)
+ */
+impl<T> List<T> {
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp)
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)))
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let mut sort = Sorting {
+            rest: self.head.take(),
+            list: &mut self.head,
+            runs: Vec::new(),
+            left: None,
+            right: None,
+            merged: None,
+        };
+        while let Some(mut node) = sort.rest.take() {
+            sort.rest = node.next.take();
+            // carried along in `right`
+            sort.right = Some(node);
+            let mut i = 0;
+            while let Some(run) = sort.runs.get_mut(i).and_then(Option::take) {
+                sort.left = Some(run);
+                sort.merge(&mut compare);
+                i += 1;
+            }
+            if i == sort.runs.len() {
+                sort.runs.push(None);
+            }
+            sort.runs[i] = sort.right.take();
+        }
+        // low indices hold the newest nodes
+        for i in 0..sort.runs.len() {
+            sort.left = sort.runs[i].take();
+            sort.merge(&mut compare);
+        }
+        *sort.list = sort.right.take();
+    }
+}
+
+// Everything a sort in progress holds. Every node is in exactly one of these
+// chains, so if `compare` panics, `drop` can find them all.
+struct Sorting<'a, T> {
+    list: &'a mut Link<T>,
+    // not looked at yet
+    rest: Link<T>,
+    runs: Vec<Link<T>>,
+    left: Link<T>,
+    right: Link<T>,
+    merged: Link<T>,
+}
+
+impl<T> Sorting<'_, T> {
+    // Stable merge of `left` and `right` into `right`: takes from `right`
+    // only if strictly smaller.
+    fn merge<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: &mut F) {
+        let Sorting { left, right, merged, .. } = self;
+        let mut tail = merged;
+        while let (Some(l), Some(r)) = (&*left, &*right) {
+            let src = if compare(&r.elem, &l.elem) == Ordering::Less { &mut *right } else { &mut *left };
+            let mut node = src.take().unwrap();
+            *src = node.next.take();
+            tail = &mut tail.insert(node).next;
+        }
+        *tail = left.take().or(right.take());
+        *right = self.merged.take();
+    }
+}
+
+// Nothing to do after a normal sort: every chain is empty by then.
+impl<T> Drop for Sorting<'_, T> {
+    fn drop(&mut self) {
+        let mut end = &mut *self.list;
+        let chains = [&mut self.merged, &mut self.left, &mut self.right, &mut self.rest];
+        for chain in chains.into_iter().chain(&mut self.runs) {
+            if chain.is_none() {
+                continue;
+            }
+            while end.is_some() {
+                end = &mut end.as_mut().unwrap().next;
+            }
+            *end = chain.take();
+        }
+    }
+}
+
+/*
+ * Cursors, modeled on std's `linked_list::{Cursor, CursorMut}`.
+ *
+ * A cursor sits *on* an element, or on the "ghost" position that isn't an
+ * element. Since we're singly-linked a cursor can only walk forward, and it
+ * can't wrap around from the end back to the front like std's does:
+ * - a new cursor starts on the ghost in front of the head,
+ * - moving past the last element parks it on a ghost behind the tail, where
+ *   it stays.
+ * Edits always happen *after* the cursor (the only O(1) spot for us), so on
+ * the front ghost they act on the head and on the back ghost they append.
+ */
+pub struct Cursor<'a, T> {
+    cur: Option<&'a T>,
+    // link following the cursor: `list.head` on the front ghost
+    next: &'a Link<T>,
+    index: usize,
+}
+
+impl<T> List<T> {
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor { cur: None, next: &self.head, index: 0 }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    // index of the current element, None on a ghost
+    pub fn index(&self) -> Option<usize> {
+        self.cur.map(|_| self.index - 1)
+    }
+
+    pub fn move_next(&mut self) {
+        match self.next {
+            Some(node) => {
+                self.cur = Some(&node.elem);
+                self.next = &node.next;
+                self.index += 1;
+            }
+            None => self.cur = None,
+        }
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        self.cur
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.next.as_ref().map(|node| &node.elem)
+    }
+}
+
+pub struct CursorMut<'a, T> {
+    // `&mut` can't be copied like the `&` in `Cursor`, so `move_next` needs
+    // to take it out of `self` => Option. It is always `Some` otherwise.
+    cur: Option<&'a mut T>,
+    next: Option<&'a mut Link<T>>,
+    index: usize,
+}
+
+impl<T> List<T> {
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { cur: None, next: Some(&mut self.head), index: 0 }
+    }
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.cur.as_ref().map(|_| self.index - 1)
+    }
+
+    pub fn move_next(&mut self) {
+        let next = self.next.take().unwrap();
+        if next.is_some() {
+            // split the borrow of the node into its two fields, so we can
+            // hold on to the element and the following link at the same time.
+            let Node { elem, next } = next.as_deref_mut().unwrap();
+            self.cur = Some(elem);
+            self.next = Some(next);
+            self.index += 1;
+        } else {
+            self.cur = None;
+            self.next = Some(next);
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.cur.as_deref_mut()
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.link().as_mut().map(|node| &mut node.elem)
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        let link = self.link();
+        *link = Some(Box::new(Node { elem, next: link.take() }));
+    }
+
+    pub fn remove_next(&mut self) -> Option<T> {
+        let link = self.link();
+        link.take().map(|node| {
+            *link = node.next;
+            node.elem
+        })
+    }
+
+    // Everything after the cursor becomes a new list.
+    pub fn split_after(&mut self) -> List<T> {
+        List { head: self.link().take() }
+    }
+
+    // Moves all of `list` in after the cursor. We don't keep a tail pointer,
+    // so this walks `list` once to find its last node: O(list.len()).
+    pub fn splice_after(&mut self, mut list: List<T>) {
+        let Some(mut first) = list.head.take() else { return };
+        let mut last = &mut first;
+        while last.next.is_some() {
+            last = last.next.as_mut().unwrap();
+        }
+        let link = self.link();
+        last.next = link.take();
+        *link = Some(first);
+    }
+
+    fn link(&mut self) -> &mut Link<T> {
+        self.next.as_deref_mut().unwrap()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
@@ -184,4 +530,215 @@ mod test {
         assert_eq!(list.peek(), Some(&"Not anymore"));
         assert_eq!(list.peek_mut(), Some(&mut "Not anymore"));
     }
+
+    #[test]
+    fn cursor_walk() {
+        let mut list = List::new();
+        list.push(3);
+        list.push(2);
+        list.push(1);
+        let mut cursor = list.cursor();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&1));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&3));
+        assert_eq!(cursor.peek_next(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        // stuck on the back ghost
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn cursor_mut_insert_remove() {
+        let mut list = List::new();
+        list.push(3);
+        list.push(1);
+
+        let mut cursor = list.cursor_mut();
+        // front ghost: acts on the head
+        cursor.insert_after(0);
+        assert_eq!(cursor.peek_next(), Some(&mut 0));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.insert_after(2);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        if let Some(v) = cursor.current() { *v = 20 }
+        cursor.move_next();
+        cursor.move_next();
+        // back ghost: appends
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 20, 3, 4]);
+
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.remove_next(), Some(0));
+        cursor.move_next();
+        assert_eq!(cursor.remove_next(), Some(20));
+        assert_eq!(cursor.remove_next(), Some(3));
+        assert_eq!(cursor.remove_next(), Some(4));
+        assert_eq!(cursor.remove_next(), None);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn cursor_mut_split_splice() {
+        let mut list = List::new();
+        for i in (1..=5).rev() {
+            list.push(i);
+        }
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let tail = cursor.split_after();
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tail.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.splice_after(tail);
+        // cursor didn't move, the spliced elements come right after it
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 3));
+        cursor.splice_after(List::new());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4, 5, 2]);
+
+        // splitting on the front ghost takes everything
+        let all = list.cursor_mut().split_after();
+        assert_eq!(list.peek(), None);
+        assert_eq!(all.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4, 5, 2]);
+    }
+
+    #[test]
+    fn std_traits() {
+        let list: List<i32> = (1..=3).collect();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", List::<i32>::default()), "[]");
+
+        let mut other = list.clone();
+        assert_eq!(list, other);
+        other.extend([4, 5]);
+        other.extend(&[6]);
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+        assert_ne!(list, other);
+        assert!(list < other);
+        assert!(std::iter::once(2).collect::<List<_>>() > other);
+        assert_eq!(list.cmp(&list.clone()), std::cmp::Ordering::Equal);
+
+        let mut sum = 0;
+        for x in &list {
+            sum += x;
+        }
+        assert_eq!(sum, 6);
+        for x in &mut other {
+            *x *= 2;
+        }
+        assert_eq!(other.peek(), Some(&2));
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        fn hash_of<T: Hash>(t: &T) -> u64 {
+            let mut h = DefaultHasher::new();
+            t.hash(&mut h);
+            h.finish()
+        }
+        let a: List<i32> = (0..10).collect();
+        let b: List<i32> = (0..10).collect();
+        assert_eq!(hash_of(&a), hash_of(&b));
+        let nested1: List<List<i32>> = vec![(1..2).collect(), (2..3).collect()].into_iter().collect();
+        let nested2: List<List<i32>> = vec![(1..3).collect()].into_iter().collect();
+        assert_ne!(hash_of(&nested1), hash_of(&nested2));
+    }
+
+    #[test]
+    fn million_nodes() {
+        // none of these may recurse per node
+        let list: List<u32> = (0..1_000_000).collect();
+        let copy = list.clone();
+        assert!(list == copy);
+        assert_eq!(list.cmp(&copy), std::cmp::Ordering::Equal);
+        drop(list);
+        drop(copy);
+    }
+
+    #[test]
+    fn sort() {
+        let mut list: List<i32> = [5, 1, 4, 2, 3, 2].into_iter().collect();
+        list.sort();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 2, 3, 4, 5]);
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![5, 4, 3, 2, 2, 1]);
+
+        let mut empty = List::<i32>::new();
+        empty.sort();
+        assert_eq!(empty.peek(), None);
+        let mut one: List<i32> = Some(1).into_iter().collect();
+        one.sort();
+        assert_eq!(one.pop(), Some(1));
+    }
+
+    #[test]
+    fn sort_stable_in_place() {
+        // (key, original position), pseudo-random keys with many duplicates
+        let mut seed = 12345u32;
+        let items: Vec<(u32, usize)> = (0..1000)
+            .map(|i| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ((seed >> 16) % 10, i)
+            })
+            .collect();
+        let mut list: List<(u32, usize)> = items.iter().copied().collect();
+        let mut addrs_before: Vec<_> = list.iter().map(|x| x as *const _).collect();
+
+        list.sort_by_key(|x| x.0);
+        let mut expected = items.clone();
+        expected.sort_by_key(|x| x.0); // std's sort is stable too
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+
+        // same nodes, just relinked
+        let mut addrs_after: Vec<_> = list.iter().map(|x| x as *const _).collect();
+        addrs_before.sort();
+        addrs_after.sort();
+        assert_eq!(addrs_before, addrs_after);
+    }
+
+    #[test]
+    fn sort_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+        // A comparator that gives up partway through sorting a long list.
+        // At that point the nodes are spread over runs of up to 2^17 nodes;
+        // dropping those recursively would overflow the stack.
+        let n = 200_000;
+        let mut list: List<u32> = (0..n).rev().collect();
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                calls += 1;
+                assert!(calls < 1_000_000, "comparator gave up");
+                a.cmp(b)
+            })
+        }));
+        assert!(result.is_err());
+        // every element is back, in some order
+        let mut elems: Vec<u32> = list.iter().copied().collect();
+        elems.sort();
+        assert!(elems.into_iter().eq(0..n));
+        list.sort();
+        assert!(list.into_iter().eq(0..n));
+    }
 }
@@ -0,0 +1,351 @@
+/*
+ * `second`'s stack with its own node allocator.
+ *
+ * `second::List` does one `Box::new` per push and one free per pop, and for
+ * a stack that's constantly pushed and popped the allocator ends up being
+ * most of the work. Here nodes come out of big blocks ("chunks") that we
+ * allocate ourselves, each as big as all the previous ones together (so the
+ * total capacity doubles), and a popped node isn't freed but goes onto a
+ * free list for the next push. Once the list has grown to its working size,
+ * push and pop don't allocate at all (see tests/arena_allocations.rs).
+ * Memory goes back to the system only on `shrink_to_fit` or drop.
+ *
+ * Nodes don't move once allocated (a chunk is never resized), so the links
+ * are plain pointers into the chunks, with the same rules as `fifth`: the
+ * chunks are held as raw pointers too and only turned back into a `Box` to
+ * be freed. A node's `elem` is only initialized while the node is on the
+ * list, not while it's on the free list.
+ */
+
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+
+pub struct List<T> {
+    head: Link<T>,
+    // popped nodes, linked through `next`, waiting to be reused
+    free: Link<T>,
+    chunks: Vec<NonNull<[MaybeUninit<Node<T>>]>>,
+    // how many slots of the last chunk have been handed out; the rest have
+    // never been used
+    used: usize,
+    capacity: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    elem: MaybeUninit<T>,
+    next: Link<T>,
+}
+
+// the first chunk, if nobody asked for a capacity
+const MIN_CHUNK: usize = 16;
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List {
+            head: None,
+            free: None,
+            chunks: Vec::new(),
+            used: 0,
+            capacity: 0,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    // Room for `capacity` elements in a single chunk, so the first that many
+    // pushes won't allocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut list = List::new();
+        if capacity > 0 {
+            list.add_chunk(capacity);
+        }
+        list
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let node = self.alloc();
+        unsafe {
+            node.as_ptr().write(Node { elem: MaybeUninit::new(elem), next: self.head });
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.head.map(|node| unsafe {
+            let node = node.as_ptr();
+            let elem = (*node).elem.assume_init_read();
+            self.head = (*node).next;
+            (*node).next = self.free;
+            self.free = NonNull::new(node);
+            self.len -= 1;
+            elem
+        })
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        unsafe { self.head.map(|node| (*node.as_ptr()).elem.assume_init_ref()) }
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.map(|node| (*node.as_ptr()).elem.assume_init_mut()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // How many elements fit without allocating.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Moves all elements into one chunk of exactly the right size and frees
+    // the rest. Nodes can't be freed one at a time (they're in the middle of
+    // chunks), so this rebuilds the list: O(n), and it allocates.
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity == self.len {
+            return;
+        }
+        let mut elems = Vec::with_capacity(self.len);
+        while let Some(elem) = self.pop() {
+            elems.push(elem);
+        }
+        // dropping the old (now empty) list frees its chunks
+        *self = List::with_capacity(elems.len());
+        for elem in elems.into_iter().rev() {
+            self.push(elem);
+        }
+    }
+
+    // An unused node: recycled if possible, else the next fresh slot.
+    fn alloc(&mut self) -> NonNull<Node<T>> {
+        if let Some(node) = self.free {
+            self.free = unsafe { (*node.as_ptr()).next };
+            return node;
+        }
+        if self.used == self.chunks.last().map_or(0, |chunk| chunk.len()) {
+            // the last chunk is used up (or there is none yet): double the
+            // total capacity
+            self.add_chunk(self.capacity.max(MIN_CHUNK));
+        }
+        let chunk = *self.chunks.last().unwrap();
+        let slot = unsafe { (chunk.as_ptr() as *mut MaybeUninit<Node<T>>).add(self.used) };
+        self.used += 1;
+        unsafe { NonNull::new_unchecked(slot as *mut Node<T>) }
+    }
+
+    fn add_chunk(&mut self, size: usize) {
+        let chunk: Box<[_]> = (0..size).map(|_| MaybeUninit::uninit()).collect();
+        self.chunks.push(NonNull::from(Box::leak(chunk)));
+        self.used = 0;
+        self.capacity += size;
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // drop the elements (iteratively), then the chunks with the nodes
+        while self.pop().is_some() {}
+        for chunk in self.chunks.drain(..) {
+            drop(unsafe { Box::from_raw(chunk.as_ptr()) });
+        }
+    }
+}
+
+// Same as `sixth::List`: the raw pointers opt us out of the auto impls.
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+/*
+ * The same three iterators as `second`, just following our pointers.
+ */
+pub struct IntoIter<T>(List<T>);
+
+pub struct Iter<'a, T> {
+    next: Link<T>,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    next: Link<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head, _marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head, _marker: PhantomData }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            self.next = node.next;
+            node.elem.assume_init_ref()
+        })
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| unsafe {
+            let node = node.as_ptr();
+            self.next = (*node).next;
+            (*node).elem.assume_init_mut()
+        })
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// $ cargo +nightly miri test second_arena
+#[cfg(test)]
+mod test {
+    use super::List;
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert_eq!(list.pop(), None);
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek(), Some(&3));
+        *list.peek_mut().unwrap() = 30;
+        assert_eq!(list.pop(), Some(30));
+        assert_eq!(list.pop(), Some(2));
+        list.push(4);
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        assert!(list.is_empty());
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list = List::new();
+        for i in 0..100 {
+            list.push(i);
+        }
+        assert!(list.iter().copied().eq((0..100).rev()));
+        for x in &mut list {
+            *x *= 2;
+        }
+        assert_eq!((&list).into_iter().nth(1), Some(&196));
+        assert!(list.into_iter().eq((0..100).rev().map(|x| x * 2)));
+    }
+
+    #[test]
+    fn recycles_nodes() {
+        let mut list = List::with_capacity(4);
+        assert_eq!(list.capacity(), 4);
+        for round in 0..10 {
+            for i in 0..4 {
+                list.push(round * 10 + i);
+            }
+            assert_eq!(list.capacity(), 4);
+            for i in (0..4).rev() {
+                assert_eq!(list.pop(), Some(round * 10 + i));
+            }
+        }
+        // growing past it adds a chunk as big as all the others together
+        for i in 0..5 {
+            list.push(i);
+        }
+        assert_eq!(list.capacity(), 4 + 16);
+        assert_eq!(list.chunks.len(), 2);
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut list = List::new();
+        for i in 0..1000 {
+            list.push(i);
+        }
+        for _ in 0..900 {
+            list.pop();
+        }
+        assert!(list.capacity() >= 1000);
+        list.shrink_to_fit();
+        assert_eq!(list.capacity(), 100);
+        assert_eq!(list.chunks.len(), 1);
+        assert!(list.iter().copied().eq((0..100).rev()));
+        list.push(100);
+        assert_eq!(list.peek(), Some(&100));
+
+        let mut empty = List::<i32>::with_capacity(10);
+        empty.shrink_to_fit();
+        assert_eq!(empty.capacity(), 0);
+    }
+
+    #[test]
+    fn drops_elements() {
+        use std::rc::Rc;
+        let shared = Rc::new(());
+        let mut list = List::new();
+        for _ in 0..50 {
+            list.push(shared.clone());
+        }
+        for _ in 0..20 {
+            list.pop();
+        }
+        // recycled nodes hold no element: 30 left + ours
+        assert_eq!(Rc::strong_count(&shared), 31);
+        drop(list);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+}
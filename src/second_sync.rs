@@ -0,0 +1,271 @@
+/*
+ * `second::List` for many threads at once: a lock-free (Treiber) stack.
+ *
+ * Same shape -- a head pointer and a chain of nodes -- but `head` is an
+ * `AtomicPtr` and push/pop take `&self`. Instead of `mem::replace`-ing the
+ * head we read it, prepare the new value, and `compare_exchange` it in; if
+ * another thread got there first the CAS fails and we retry with the fresh
+ * head. Nobody ever waits on a lock, so a descheduled thread can't block the
+ * others.
+ *
+ * R. K. Treiber, "Systems Programming: Coping with Parallelism" (1986).
+ *
+ * The tricky part is `pop`: between reading `head` and reading `head.next`
+ * another thread may pop *and free* that node. Popped nodes are therefore
+ * retired to a hazard pointer domain (see hazard.rs) instead of being freed
+ * on the spot.
+ */
+
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::hazard::Domain;
+
+pub struct List<T> {
+    head: AtomicPtr<Node<T>>,
+    hazards: Domain<Node<T>>,
+    // raw pointers only => we'd be Send + Sync for any T, see impls below.
+    _marker: PhantomData<T>,
+}
+
+struct Node<T> {
+    // moved out by `pop` while the node itself waits for reclamation, so the
+    // domain must not drop it again.
+    elem: ManuallyDrop<T>,
+    // never changes once the node is published
+    next: *mut Node<T>,
+}
+
+// Elements go in on one thread and come out on another: that's what Send is
+// about. We never hand out `&T` across threads (see `peek_copied`), so
+// T: Sync isn't needed, not even for sharing the list itself.
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Send> Sync for List<T> {}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: AtomicPtr::new(ptr::null_mut()), hazards: Domain::new(), _marker: PhantomData }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // not published yet, nobody else can see the node
+            unsafe { (*node).next = head; }
+            // Release: whoever Acquire-loads `node` from head sees its fields
+            match self.head.compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let hazard = self.hazards.hazard();
+        loop {
+            let head = hazard.protect(&self.head);
+            if head.is_null() {
+                return None;
+            }
+            // protected => not freed, even if someone pops it right now.
+            // That also rules out ABA: its address can't be reused while
+            // we're holding it, so the CAS below can't be fooled.
+            let next = unsafe { (*head).next };
+            if self.head
+                .compare_exchange(head, next, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                // we unlinked it, so the element is ours
+                let elem = unsafe { ptr::read(&*(*head).elem) };
+                hazard.reset();
+                unsafe { self.hazards.retire(head) };
+                return Some(elem);
+            }
+        }
+    }
+
+    // Not `second::List::peek`: that returns `&T`, and we can't, not even
+    // behind a guard holding the hazard. A hazard keeps the *node* alive, but
+    // `pop` moves the element out to its caller, who may drop it (free the
+    // String, say) while we'd still be looking at it. The only sound thing is
+    // to copy the element out, and only a `Copy` one: `Clone` could run on a
+    // value that's already been dropped. Copy types own nothing and have no
+    // destructor, so a copy taken mid-pop is just an ordinary value.
+    pub fn peek_copied(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        let hazard = self.hazards.hazard();
+        let head = hazard.protect(&self.head);
+        unsafe { head.as_ref().map(|node| *node.elem) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // &mut self: no other thread can be in push/pop anymore.
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            cur = node.next;
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+        }
+        // retired nodes (elements already moved out) go with `self.hazards`
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert_eq!(list.pop(), None);
+        assert_eq!(list.peek_copied(), None);
+        assert!(list.is_empty());
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.peek_copied(), Some(3));
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+
+        list.push(4);
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<List<i32>>();
+        is_send_sync::<List<std::cell::Cell<i32>>>();
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let list = List::new();
+        for _ in 0..200 {
+            list.push(Counted);
+        }
+        for _ in 0..150 {
+            drop(list.pop());
+        }
+        assert_eq!(DROPS.load(Ordering::Relaxed), 150);
+        drop(list);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 200);
+    }
+
+    #[test]
+    fn retired_nodes_get_freed() {
+        let list = List::new();
+        for i in 0..10_000 {
+            list.push(i);
+            assert_eq!(list.pop(), Some(i));
+        }
+        // nobody holds a hazard => every scan frees all it finds
+        assert!(list.hazards.retired_count() < 64);
+    }
+
+    #[test]
+    fn stress_push_then_pop() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 2_000;
+        let list = List::new();
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let list = &list;
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        list.push(t * PER_THREAD + i);
+                    }
+                });
+            }
+        });
+        let popped: Vec<Vec<usize>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let list = &list;
+                    s.spawn(move || {
+                        let mut mine = vec![];
+                        while let Some(x) = list.pop() {
+                            mine.push(x);
+                        }
+                        mine
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut all: Vec<usize> = popped.into_iter().flatten().collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn stress_interleaved() {
+        // every thread pushes and pops at the same time, nothing may be lost
+        // or handed out twice.
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 5_000;
+        let list = List::new();
+        let popped: Vec<Vec<usize>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let list = &list;
+                    s.spawn(move || {
+                        let mut mine = vec![];
+                        for i in 0..PER_THREAD {
+                            list.push(t * PER_THREAD + i);
+                            if i % 2 == 1 {
+                                mine.extend(list.pop());
+                                mine.extend(list.pop());
+                            }
+                            // exercise peek_copied's hazard against pops
+                            let _ = list.peek_copied();
+                        }
+                        mine
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut all: Vec<usize> = popped.into_iter().flatten().collect();
+        while let Some(x) = list.pop() {
+            all.push(x);
+        }
+        all.sort_unstable();
+        assert_eq!(all, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+}
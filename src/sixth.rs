@@ -0,0 +1,988 @@
+/*
+ * A production-quality unsafe doubly-linked deque.
+ *
+ * `fourth` showed that a safe doubly-linked list is possible, but every link
+ * is an `Rc<RefCell<_>>`: two reference counts and a borrow flag per node,
+ * runtime borrow checks on every access, and `pop` can panic if a node is
+ * unexpectedly shared. Here we do what std's `LinkedList` does instead and
+ * manage the nodes ourselves through `NonNull` pointers, the same "only raw
+ * pointers" rule as in `fifth`.
+ *
+ * `NonNull<T>` is `*mut T` that's never null (so `Option<NonNull<T>>` is
+ * pointer-sized) and *covariant* in `T`, like `&T`: a `List<&'static str>`
+ * can be used where a `List<&'a str>` is expected. `*mut T` would make us
+ * invariant, see the `variance` test below.
+ *
+ * Invariants:
+ * - `head` is None <=> `tail` is None <=> `len == 0`,
+ * - `head.prev` and `tail.next` are None,
+ * - for every node n, `n.next.prev == n` and `n.prev.next == n`.
+ */
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::NonNull;
+
+pub struct List<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    // We own `T`s (drop check) even though we only hold pointers.
+    _marker: PhantomData<T>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    prev: Link<T>,
+    next: Link<T>,
+    elem: T,
+}
+
+impl<T> Node<T> {
+    fn raw(elem: T) -> NonNull<Self> {
+        NonNull::from(Box::leak(Box::new(Node { prev: None, next: None, elem })))
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None, tail: None, len: 0, _marker: PhantomData }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let new = Node::raw(elem);
+        unsafe {
+            match self.head {
+                Some(old) => {
+                    (*old.as_ptr()).prev = Some(new);
+                    (*new.as_ptr()).next = Some(old);
+                }
+                None => self.tail = Some(new),
+            }
+        }
+        self.head = Some(new);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let new = Node::raw(elem);
+        unsafe {
+            match self.tail {
+                Some(old) => {
+                    (*old.as_ptr()).next = Some(new);
+                    (*new.as_ptr()).prev = Some(old);
+                }
+                None => self.head = Some(new),
+            }
+        }
+        self.tail = Some(new);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.head = boxed.next;
+            match self.head {
+                Some(new) => (*new.as_ptr()).prev = None,
+                None => self.tail = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.tail = boxed.prev;
+            match self.tail {
+                Some(new) => (*new.as_ptr()).next = None,
+                None => self.head = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == x)
+    }
+
+    // Moves all of `other` to our back in O(1), leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        match (self.tail, other.head) {
+            (_, None) => {}
+            (None, Some(_)) => mem::swap(self, other),
+            (Some(tail), Some(head)) => unsafe {
+                (*tail.as_ptr()).next = Some(head);
+                (*head.as_ptr()).prev = Some(tail);
+                self.tail = other.tail.take();
+                self.len += mem::replace(&mut other.len, 0);
+                other.head = None;
+            },
+        }
+    }
+
+    // Splits off `[at, len)` into a new list. O(min(at, len - at)).
+    // Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split_off index (is {}) should be <= len (is {})", at, self.len);
+        if at == 0 {
+            return mem::take(self);
+        }
+        if at == self.len {
+            return List::new();
+        }
+        // walk from whichever end is closer to the node at `at`
+        let mut cursor = if at <= self.len / 2 {
+            let mut c = self.cursor_front_mut();
+            for _ in 0..at {
+                c.move_next();
+            }
+            c
+        } else {
+            let mut c = self.cursor_back_mut();
+            for _ in at..c.list.len - 1 {
+                c.move_prev();
+            }
+            c
+        };
+        let front = cursor.split_before();
+        mem::replace(self, front)
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// Just like `Vec`/`Box`: sending the list sends the `T`s, sharing `&List`
+// shares `&T`s. The raw pointers opt us out of the auto impls.
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+/*
+ * Iterators.
+ *
+ * Both ends can be walked towards each other; `len` tells us when they've
+ * met, so we never have to compare the pointers (and `size_hint` is exact).
+ */
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { head: self.head, tail: self.tail, len: self.len, _marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { head: self.head, tail: self.tail, len: self.len, _marker: PhantomData }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &(*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &(*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// Iter is morally a `&List`, IterMut a `&mut List`.
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+/*
+ * Standard traits, all going through the iterators (no recursion).
+ */
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for List<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: PartialOrd> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+/*
+ * Cursors, with the same semantics as std's `linked_list::CursorMut`: the
+ * cursor sits on an element or on the "ghost" non-element between tail and
+ * head, so it can wrap around in both directions. Unlike `second`'s cursor
+ * all edits are O(1) on both sides.
+ */
+pub struct CursorMut<'a, T> {
+    list: &'a mut List<T>,
+    cur: Link<T>,
+    index: Option<usize>,
+}
+
+impl<T> List<T> {
+    // starts on the ghost
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { list: self, cur: None, index: None }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        let cur = self.head;
+        CursorMut { index: cur.map(|_| 0), list: self, cur }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let cur = self.tail;
+        CursorMut { index: cur.map(|_| self.len - 1), list: self, cur }
+    }
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).next;
+                self.index = match self.cur {
+                    Some(_) => self.index.map(|i| i + 1),
+                    None => None,
+                };
+            },
+            // ghost wraps around to the front
+            None => {
+                self.cur = self.list.head;
+                self.index = self.cur.map(|_| 0);
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).prev;
+                self.index = match self.cur {
+                    Some(_) => self.index.map(|i| i - 1),
+                    None => None,
+                };
+            },
+            None => {
+                self.cur = self.list.tail;
+                self.index = self.cur.map(|_| self.list.len - 1);
+            }
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = match self.cur {
+                Some(cur) => (*cur.as_ptr()).next,
+                None => self.list.head,
+            };
+            next.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = match self.cur {
+                Some(cur) => (*cur.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            prev.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    // On the ghost this pushes to the front.
+    pub fn insert_after(&mut self, elem: T) {
+        let Some(cur) = self.cur else {
+            return self.list.push_front(elem);
+        };
+        unsafe {
+            let new = Node::raw(elem);
+            let next = (*cur.as_ptr()).next;
+            (*new.as_ptr()).prev = Some(cur);
+            (*new.as_ptr()).next = next;
+            (*cur.as_ptr()).next = Some(new);
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(new),
+                None => self.list.tail = Some(new),
+            }
+        }
+        self.list.len += 1;
+    }
+
+    // On the ghost this pushes to the back.
+    pub fn insert_before(&mut self, elem: T) {
+        let Some(cur) = self.cur else {
+            return self.list.push_back(elem);
+        };
+        unsafe {
+            let new = Node::raw(elem);
+            let prev = (*cur.as_ptr()).prev;
+            (*new.as_ptr()).next = Some(cur);
+            (*new.as_ptr()).prev = prev;
+            (*cur.as_ptr()).prev = Some(new);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(new),
+                None => self.list.head = Some(new),
+            }
+        }
+        self.list.len += 1;
+        self.index = self.index.map(|i| i + 1);
+    }
+
+    // Removes the current element and moves on to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cur = self.cur?;
+        unsafe {
+            let boxed = Box::from_raw(cur.as_ptr());
+            match boxed.prev {
+                Some(prev) => (*prev.as_ptr()).next = boxed.next,
+                None => self.list.head = boxed.next,
+            }
+            match boxed.next {
+                Some(next) => (*next.as_ptr()).prev = boxed.prev,
+                None => self.list.tail = boxed.prev,
+            }
+            self.list.len -= 1;
+            self.cur = boxed.next;
+            if self.cur.is_none() {
+                self.index = None;
+            }
+            Some(boxed.elem)
+        }
+    }
+
+    // Everything before the cursor becomes a new list. On the ghost that's
+    // the whole list.
+    pub fn split_before(&mut self) -> List<T> {
+        let Some(cur) = self.cur else {
+            return mem::take(self.list);
+        };
+        let before_len = self.index.unwrap();
+        unsafe {
+            let prev = (*cur.as_ptr()).prev.take();
+            let front = List {
+                head: prev.map(|_| self.list.head.unwrap()),
+                tail: prev,
+                len: before_len,
+                _marker: PhantomData,
+            };
+            if let Some(prev) = prev {
+                (*prev.as_ptr()).next = None;
+            }
+            self.list.head = Some(cur);
+            self.list.len -= before_len;
+            self.index = Some(0);
+            front
+        }
+    }
+
+    // Everything after the cursor becomes a new list. On the ghost that's
+    // the whole list.
+    pub fn split_after(&mut self) -> List<T> {
+        let Some(cur) = self.cur else {
+            return mem::take(self.list);
+        };
+        let after_len = self.list.len - self.index.unwrap() - 1;
+        unsafe {
+            let next = (*cur.as_ptr()).next.take();
+            let back = List {
+                head: next,
+                tail: next.map(|_| self.list.tail.unwrap()),
+                len: after_len,
+                _marker: PhantomData,
+            };
+            if let Some(next) = next {
+                (*next.as_ptr()).prev = None;
+            }
+            self.list.tail = Some(cur);
+            self.list.len -= after_len;
+            back
+        }
+    }
+
+    // Moves all of `other` in right before the cursor (at the back on the
+    // ghost). O(1).
+    pub fn splice_before(&mut self, mut other: List<T>) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        let len = mem::replace(&mut other.len, 0);
+        unsafe {
+            let prev = match self.cur {
+                Some(cur) => (*cur.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            (*head.as_ptr()).prev = prev;
+            (*tail.as_ptr()).next = self.cur;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(head),
+                None => self.list.head = Some(head),
+            }
+            match self.cur {
+                Some(cur) => (*cur.as_ptr()).prev = Some(tail),
+                None => self.list.tail = Some(tail),
+            }
+        }
+        self.list.len += len;
+        self.index = self.index.map(|i| i + len);
+    }
+
+    // Moves all of `other` in right after the cursor (at the front on the
+    // ghost). O(1).
+    pub fn splice_after(&mut self, mut other: List<T>) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        let len = mem::replace(&mut other.len, 0);
+        unsafe {
+            let next = match self.cur {
+                Some(cur) => (*cur.as_ptr()).next,
+                None => self.list.head,
+            };
+            (*tail.as_ptr()).next = next;
+            (*head.as_ptr()).prev = self.cur;
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(tail),
+                None => self.list.tail = Some(tail),
+            }
+            match self.cur {
+                Some(cur) => (*cur.as_ptr()).next = Some(head),
+                None => self.list.head = Some(head),
+            }
+        }
+        self.list.len += len;
+    }
+}
+
+unsafe impl<T: Send> Send for CursorMut<'_, T> {}
+unsafe impl<T: Sync> Sync for CursorMut<'_, T> {}
+
+// $ cargo +nightly miri test sixth
+#[cfg(test)]
+mod test {
+    use super::List;
+
+    fn list_from<T: Clone>(v: &[T]) -> List<T> {
+        v.iter().cloned().collect()
+    }
+
+    fn check_links<T: Eq + std::fmt::Debug>(list: &List<T>) {
+        // walking both ways must agree with each other and with `len`
+        let forward: Vec<_> = list.iter().collect();
+        let mut backward: Vec<_> = list.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        assert_eq!(list.head.is_none(), list.is_empty());
+        assert_eq!(list.tail.is_none(), list.is_empty());
+    }
+
+    #[test]
+    fn test_basic_front() {
+        let mut list = List::new();
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+
+        list.push_front(10);
+        list.push_front(20);
+        list.push_front(30);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(30));
+        list.push_front(40);
+        assert_eq!(list.pop_front(), Some(40));
+        assert_eq!(list.pop_front(), Some(20));
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_basic_back() {
+        let mut list = List::new();
+        assert_eq!(list.pop_back(), None);
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&2));
+        *list.front_mut().unwrap() = -1;
+        *list.back_mut().unwrap() = 20;
+        assert_eq!(list.pop_back(), Some(20));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), Some(-1));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.front(), None);
+        assert_eq!(list.back(), None);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut list = list_from(&[0, 1, 2, 3, 4, 5, 6]);
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&3));
+        // met in the middle
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+
+        for x in list.iter_mut().rev() {
+            *x *= 10;
+        }
+        assert_eq!(list.iter_mut().len(), 7);
+        assert_eq!(list, list_from(&[0, 10, 20, 30, 40, 50, 60]));
+        let v: Vec<_> = list.into_iter().rev().collect();
+        assert_eq!(v, vec![60, 50, 40, 30, 20, 10, 0]);
+    }
+
+    #[test]
+    fn test_std_traits() {
+        let list = list_from(&[1, 2, 3]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(list.clone(), list);
+        assert!(list < list_from(&[1, 2, 4]));
+        assert!(list > list_from(&[1, 2]));
+        assert_ne!(list, list_from(&[1, 2]));
+        assert!(list.contains(&2));
+        assert!(!list.contains(&4));
+
+        use std::collections::HashSet;
+        let mut set = HashSet::new();
+        set.insert(list.clone());
+        assert!(set.contains(&list));
+        assert!(!set.contains(&list_from(&[3, 2, 1])));
+
+        let mut l2 = List::new();
+        l2.extend(&[1, 2]);
+        l2.extend(vec![3]);
+        assert_eq!(l2, list);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = list_from(&[1, 2]);
+        let mut b = list_from(&[3, 4]);
+        a.append(&mut b);
+        assert_eq!(a, list_from(&[1, 2, 3, 4]));
+        assert!(b.is_empty());
+        check_links(&a);
+        check_links(&b);
+
+        // empty on either side
+        a.append(&mut b);
+        assert_eq!(a.len(), 4);
+        b.append(&mut a);
+        assert_eq!(b, list_from(&[1, 2, 3, 4]));
+        assert!(a.is_empty());
+        check_links(&a);
+        check_links(&b);
+
+        // both still usable
+        a.push_back(5);
+        b.push_back(6);
+        assert_eq!(b.pop_front(), Some(1));
+        assert_eq!(a.pop_back(), Some(5));
+    }
+
+    #[test]
+    fn test_split_off() {
+        for at in 0..=6 {
+            let mut list = list_from(&[0, 1, 2, 3, 4, 5]);
+            let back = list.split_off(at);
+            check_links(&list);
+            check_links(&back);
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..at).collect::<Vec<_>>());
+            assert_eq!(back.iter().copied().collect::<Vec<_>>(), (at..6).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_oob() {
+        list_from(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn test_cursor_move_peek() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 6));
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(1));
+
+        let mut cursor = list.cursor_back_mut();
+        assert_eq!(cursor.current(), Some(&mut 6));
+        assert_eq!(cursor.index(), Some(5));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.index(), Some(4));
+    }
+
+    #[test]
+    fn test_cursor_mut_insert() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_mut();
+        cursor.splice_before(Some(7).into_iter().collect());
+        cursor.splice_after(Some(8).into_iter().collect());
+        check_links(&list);
+        assert_eq!(list, list_from(&[8, 1, 2, 3, 4, 5, 6, 7]));
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_prev();
+        let tmp = cursor.split_before();
+        assert_eq!(tmp, list_from(&[8, 1, 2, 3, 4, 5, 6, 7]));
+        list = tmp;
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_after(9);
+        cursor.insert_before(10);
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(3));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 9));
+        assert_eq!(cursor.index(), Some(3));
+        check_links(&list);
+        assert_eq!(list, list_from(&[8, 1, 10, 9, 3, 4, 5, 6, 7]));
+
+        let mut cursor = list.cursor_front_mut();
+        let mut p: List<u32> = List::new();
+        p.extend([100, 101, 102, 103]);
+        let mut q: List<u32> = List::new();
+        q.extend([200, 201, 202, 203]);
+        cursor.splice_after(p);
+        cursor.splice_before(q);
+        assert_eq!(cursor.index(), Some(4));
+        check_links(&list);
+        assert_eq!(
+            list,
+            list_from(&[200, 201, 202, 203, 8, 100, 101, 102, 103, 1, 10, 9, 3, 4, 5, 6, 7])
+        );
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_prev();
+        let tmp = cursor.split_before();
+        assert_eq!(tmp.len(), 17);
+        assert!(list.is_empty());
+        list = tmp;
+
+        let mut cursor = list.cursor_front_mut();
+        for _ in 0..5 {
+            cursor.move_next();
+        }
+        let tmp = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(tmp, list_from(&[200, 201, 202, 203, 8]));
+        let tail = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 100));
+        check_links(&tmp);
+        check_links(&tail);
+        check_links(&list);
+        assert_eq!(list, list_from(&[100]));
+        assert_eq!(tail, list_from(&[101, 102, 103, 1, 10, 9, 3, 4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn test_cursor_remove_to_empty() {
+        let mut list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.insert_after(4);
+        cursor.insert_before(5);
+        check_links(&list);
+        assert_eq!(list, list_from(&[4, 5]));
+    }
+
+    #[test]
+    fn test_drop_counts() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+        let drops = Rc::new(Cell::new(0));
+        let mut list = List::new();
+        for _ in 0..10 {
+            list.push_back(Counted(drops.clone()));
+        }
+        let back = list.split_off(4);
+        drop(list.pop_front());
+        assert_eq!(drops.get(), 1);
+        let mut iter = back.into_iter();
+        iter.next_back();
+        assert_eq!(drops.get(), 2);
+        drop(iter);
+        assert_eq!(drops.get(), 7);
+        drop(list);
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[allow(dead_code)]
+    fn variance() {
+        // compiles only if all of these are covariant in T
+        fn a<'a>(x: List<&'static str>) -> List<&'a str> { x }
+        fn b<'i, 'a>(x: super::Iter<'i, &'static str>) -> super::Iter<'i, &'a str> { x }
+        fn c<'a>(x: super::IntoIter<&'static str>) -> super::IntoIter<&'a str> { x }
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<List<i32>>();
+        is_send_sync::<super::Iter<'static, i32>>();
+        is_send_sync::<super::IterMut<'static, i32>>();
+        is_send_sync::<super::IntoIter<i32>>();
+        is_send_sync::<super::CursorMut<'static, i32>>();
+    }
+}
@@ -27,7 +27,7 @@ impl<T> List<T> {
 
     pub fn prepend(&self, elem: T) -> List<T> {
         List { head: Some(Rc::new(Node {
-            elem: elem,
+            elem,
             next: self.head.clone()
         })) }
     }
@@ -70,6 +70,320 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+// Just a position in the list: a copy walks on independently.
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter { next: self.next }
+    }
+}
+
+// Cloning a persistent list is just another handle on the same nodes.
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List { head: self.head.clone() }
+    }
+}
+
+/*
+ * Persistent operations.
+ *
+ * Nothing is ever modified, so a result can reuse (share) any *suffix* of an
+ * input as-is; only nodes in front of the shared part have to be new. Each
+ * method notes what it shares and what it copies. All of them are loops,
+ * never recursion per node: new prefixes are collected first and then
+ * prepended back-to-front onto the shared suffix.
+ */
+impl<T> List<T> {
+    // Shares nothing, copies nothing.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.iter().nth(n)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.iter().last()
+    }
+
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == x)
+    }
+
+    pub fn fold<B, F: FnMut(B, &T) -> B>(&self, init: B, f: F) -> B {
+        self.iter().fold(init, f)
+    }
+
+    // Shares everything from index `n` on; copies nothing. O(n).
+    pub fn drop(&self, n: usize) -> List<T> {
+        let mut link = &self.head;
+        for _ in 0..n {
+            match link {
+                Some(node) => link = &node.next,
+                None => break,
+            }
+        }
+        List { head: link.clone() }
+    }
+
+    // Copies the first `n` elements. A prefix can't share with the original
+    // (its last node would need a different `next`), unless it *is* the
+    // whole list: then everything is shared.
+    pub fn take(&self, n: usize) -> List<T>
+    where
+        T: Clone,
+    {
+        if self.drop(n).is_empty() {
+            return self.clone();
+        }
+        List::prepend_all(self.iter().take(n).cloned().collect(), List::new())
+    }
+
+    // `(take(n), drop(n))`: copies the first `n`, shares the rest.
+    pub fn split_at(&self, n: usize) -> (List<T>, List<T>)
+    where
+        T: Clone,
+    {
+        (self.take(n), self.drop(n))
+    }
+
+    // Copies `self`, shares all of `other`.
+    pub fn append(&self, other: &List<T>) -> List<T>
+    where
+        T: Clone,
+    {
+        List::prepend_all(self.iter().cloned().collect::<Vec<_>>(), other.clone())
+    }
+
+    // Copies everything (every `next` pointer changes).
+    pub fn reverse(&self) -> List<T>
+    where
+        T: Clone,
+    {
+        self.iter().fold(List::new(), |rev, elem| rev.prepend(elem.clone()))
+    }
+
+    // Copies everything (every element changes).
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> List<U> {
+        List::prepend_all(self.iter().map(f).collect::<Vec<_>>(), List::new())
+    }
+
+    // Shares the longest suffix where every element is kept, copies the kept
+    // elements in front of it. Calls `f` once per element, front to back.
+    pub fn filter<F: FnMut(&T) -> bool>(&self, mut f: F) -> List<T>
+    where
+        T: Clone,
+    {
+        let keep: Vec<bool> = self.iter().map(&mut f).collect();
+        // everything after the last dropped element can be shared
+        let shared_from = keep.iter().rposition(|k| !k).map_or(0, |i| i + 1);
+        let prefix: Vec<T> = self.iter()
+            .zip(&keep)
+            .take(shared_from)
+            .filter(|(_, &k)| k)
+            .map(|(e, _)| e.clone())
+            .collect();
+        List::prepend_all(prefix, self.drop(shared_from))
+    }
+
+    // Copies into pairs, up to the shorter of the two.
+    pub fn zip<U>(&self, other: &List<U>) -> List<(T, U)>
+    where
+        T: Clone,
+        U: Clone,
+    {
+        let pairs: Vec<(T, U)> = self.iter().cloned().zip(other.iter().cloned()).collect();
+        List::prepend_all(pairs, List::new())
+    }
+
+    // `items` in order, in front of `suffix`.
+    fn prepend_all(items: Vec<T>, suffix: List<T>) -> List<T> {
+        items.into_iter().rev().fold(suffix, |list, elem| list.prepend(elem))
+    }
+}
+
+/*
+ * Copy-on-write mutation.
+ *
+ * Sometimes nobody else holds our nodes and copying them would be a waste.
+ * `Rc::make_mut` gives a `&mut` to a node if its count is 1 and otherwise
+ * replaces our handle with a fresh copy first. Copying a node clones its
+ * `next` handle, which makes the following node shared in turn: once we
+ * hit a shared node, everything we walk past after it gets copied too,
+ * while whatever we don't walk past stays shared. Other versions never see
+ * a change.
+ */
+impl<T: Clone> Clone for Node<T> {
+    fn clone(&self) -> Self {
+        Node { elem: self.elem.clone(), next: self.next.clone() }
+    }
+}
+
+impl<T: Clone> List<T> {
+    // Copies the first node if it's shared.
+    pub fn head_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut Rc::make_mut(node).elem)
+    }
+
+    // Copies the shared nodes among the first `i + 1`; the rest stays
+    // shared.
+    //
+    // Panics if `i >= len`.
+    pub fn set(&mut self, i: usize, elem: T) {
+        if self.nth(i).is_none() {
+            panic!("set index (is {}) should be < len (is {})", i, self.len());
+        }
+        let mut link = &mut self.head;
+        for _ in 0..i {
+            link = &mut Rc::make_mut(link.as_mut().unwrap()).next;
+        }
+        Rc::make_mut(link.as_mut().unwrap()).elem = elem;
+    }
+
+    // Copies (only) the shared nodes it walks past, as it goes.
+    pub fn iter_mut_cow(&mut self) -> IterMutCow<'_, T> {
+        IterMutCow { next: Some(&mut self.head) }
+    }
+
+    // Moves the head element out if nobody else holds the first node;
+    // otherwise clones it. Either way the rest stays shared.
+    pub fn pop_front_unique(&mut self) -> Option<T> {
+        let node = self.head.take()?;
+        match Rc::try_unwrap(node) {
+            Ok(mut node) => {
+                self.head = node.next.take();
+                Some(node.elem)
+            }
+            Err(node) => {
+                self.head = node.next.clone();
+                Some(node.elem.clone())
+            }
+        }
+    }
+}
+
+pub struct IterMutCow<'a, T> {
+    next: Option<&'a mut Link<T>>,
+}
+
+impl<'a, T: Clone> Iterator for IterMutCow<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = Rc::make_mut(self.next.take()?.as_mut()?);
+        self.next = Some(&mut node.next);
+        Some(&mut node.elem)
+    }
+}
+
+/*
+ * Introspection: how do two versions relate?
+ *
+ * Two versions of a history always end in a common suffix (possibly just
+ * the empty list) made of the very same nodes; everything in front of it
+ * differs. Nodes are compared by identity (`Rc::ptr_eq`), not by value:
+ * two separately built `[1, 2]`s share nothing.
+ */
+impl<T> List<T> {
+    // The longest suffix whose nodes `self` and `other` share. O(n + m).
+    pub fn shared_suffix(&self, other: &List<T>) -> List<T> {
+        List { head: self.split_shared(other).2.clone() }
+    }
+
+    // Nodes only reachable through this handle vs. ones somebody else holds
+    // too. Once a node is shared, so is everything after it.
+    pub fn sharing(&self) -> Sharing {
+        let mut unique = 0;
+        let mut link = &self.head;
+        while let Some(node) = link {
+            if Rc::strong_count(node) > 1 {
+                break;
+            }
+            unique += 1;
+            link = &node.next;
+        }
+        Sharing { unique, shared: self.len() - unique }
+    }
+
+    // How to get from `self` to `other`: remove `self`'s differing prefix,
+    // then insert `other`'s, both listed front to back.
+    pub fn diff<'a>(&'a self, other: &'a List<T>) -> Vec<Edit<'a, T>> {
+        let (removed, inserted, _) = self.split_shared(other);
+        self.iter().take(removed).map(Edit::Remove)
+            .chain(other.iter().take(inserted).map(Edit::Insert))
+            .collect()
+    }
+
+    // Applies a `diff` made against a version with the same front as
+    // `self`. Shares everything after the removed prefix.
+    pub fn patch(&self, edits: &[Edit<'_, T>]) -> List<T>
+    where
+        T: Clone,
+    {
+        let removed = edits.iter().filter(|e| matches!(e, Edit::Remove(_))).count();
+        let inserted: Vec<T> = edits.iter()
+            .filter_map(|e| match e {
+                Edit::Insert(elem) => Some((*elem).clone()),
+                Edit::Remove(_) => None,
+            })
+            .collect();
+        List::prepend_all(inserted, self.drop(removed))
+    }
+
+    // (length of `self`'s differing prefix, length of `other`'s, the shared
+    // suffix). Both lists end at the same place, so after skipping the
+    // length difference we can walk them in lockstep.
+    fn split_shared<'a>(&'a self, other: &'a List<T>) -> (usize, usize, &'a Link<T>) {
+        let (len_a, len_b) = (self.len(), other.len());
+        let (mut a, mut b) = (&self.head, &other.head);
+        let (mut i, mut j) = (0, 0);
+        while len_a - i > len_b - j {
+            a = &a.as_ref().unwrap().next;
+            i += 1;
+        }
+        while len_b - j > len_a - i {
+            b = &b.as_ref().unwrap().next;
+            j += 1;
+        }
+        loop {
+            match (a, b) {
+                (Some(x), Some(y)) if !Rc::ptr_eq(x, y) => {
+                    a = &x.next;
+                    b = &y.next;
+                    i += 1;
+                    j += 1;
+                }
+                _ => return (i, j, a),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sharing {
+    pub unique: usize,
+    pub shared: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit<'a, T> {
+    Remove(&'a T),
+    Insert(&'a T),
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut head = self.head.take();
@@ -87,7 +401,24 @@ impl<T> Drop for List<T> {
 
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{Edit, List, Sharing};
+    use std::rc::Rc;
+
+    fn from_slice<T: Clone>(v: &[T]) -> List<T> {
+        v.iter().rev().fold(List::new(), |l, x| l.prepend(x.clone()))
+    }
+
+    fn to_vec<T: Clone>(l: &List<T>) -> Vec<T> {
+        l.iter().cloned().collect()
+    }
+
+    // does `a` (from some node on) share its nodes with `b`?
+    fn shares<T>(a: &List<T>, b: &List<T>) -> bool {
+        match (&a.head, &b.head) {
+            (Some(x), Some(y)) => Rc::ptr_eq(x, y),
+            _ => false,
+        }
+    }
 
     #[test]
     fn iter() {
@@ -109,4 +440,217 @@ mod test {
 
         assert_eq!(l2.tail().head(), Some(&2));
     }
+
+    #[test]
+    fn queries() {
+        let list = from_slice(&[1, 2, 3, 4]);
+        assert_eq!(list.len(), 4);
+        assert!(!list.is_empty());
+        assert!(List::<i32>::new().is_empty());
+        assert_eq!(list.nth(0), Some(&1));
+        assert_eq!(list.nth(3), Some(&4));
+        assert_eq!(list.nth(4), None);
+        assert_eq!(list.last(), Some(&4));
+        assert_eq!(List::<i32>::new().last(), None);
+        assert!(list.contains(&3));
+        assert!(!list.contains(&5));
+        assert_eq!(list.fold(0, |acc, x| acc * 10 + x), 1234);
+    }
+
+    #[test]
+    fn drop_take_split() {
+        let list = from_slice(&[1, 2, 3, 4]);
+        let rest = list.drop(2);
+        assert_eq!(to_vec(&rest), vec![3, 4]);
+        assert!(shares(&rest, &list.tail().tail()));
+        assert!(list.drop(10).is_empty());
+        assert!(shares(&list.drop(0), &list));
+
+        let front = list.take(2);
+        assert_eq!(to_vec(&front), vec![1, 2]);
+        assert!(!shares(&front, &list));
+        // taking it all is free
+        assert!(shares(&list.take(4), &list));
+        assert!(shares(&list.take(10), &list));
+        assert!(list.take(0).is_empty());
+
+        let (a, b) = list.split_at(1);
+        assert_eq!(to_vec(&a), vec![1]);
+        assert_eq!(to_vec(&b), vec![2, 3, 4]);
+        assert!(shares(&b, &list.tail()));
+        // the original is untouched
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn append_reverse() {
+        let left = from_slice(&[1, 2]);
+        let right = from_slice(&[3, 4]);
+        let both = left.append(&right);
+        assert_eq!(to_vec(&both), vec![1, 2, 3, 4]);
+        assert!(shares(&both.drop(2), &right));
+        assert!(!shares(&both, &left));
+        assert!(shares(&List::new().append(&right), &right));
+
+        assert_eq!(to_vec(&both.reverse()), vec![4, 3, 2, 1]);
+        assert!(List::<i32>::new().reverse().is_empty());
+        assert_eq!(to_vec(&left), vec![1, 2]);
+    }
+
+    #[test]
+    fn map_filter_zip() {
+        let list = from_slice(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(to_vec(&list.map(|x| x * 10)), vec![10, 20, 30, 40, 50, 60]);
+
+        let evens = list.filter(|x| x % 2 == 0);
+        assert_eq!(to_vec(&evens), vec![2, 4, 6]);
+        assert!(shares(&evens.drop(2), &list.drop(5)));
+        // nothing dropped => everything shared
+        assert!(shares(&list.filter(|_| true), &list));
+        assert!(list.filter(|_| false).is_empty());
+        let big = list.filter(|x| *x != 2);
+        assert_eq!(to_vec(&big), vec![1, 3, 4, 5, 6]);
+        assert!(shares(&big.drop(1), &list.drop(2)));
+
+        let mut calls = vec![];
+        list.filter(|x| {
+            calls.push(*x);
+            true
+        });
+        assert_eq!(calls, vec![1, 2, 3, 4, 5, 6]);
+
+        let names = from_slice(&["a", "b", "c"]);
+        assert_eq!(to_vec(&list.zip(&names)), vec![(1, "a"), (2, "b"), (3, "c")]);
+    }
+
+    // addresses of the nodes, front to back
+    fn nodes<T>(l: &List<T>) -> Vec<*const super::Node<T>> {
+        let mut nodes = vec![];
+        let mut link = &l.head;
+        while let Some(node) = link {
+            nodes.push(Rc::as_ptr(node));
+            link = &node.next;
+        }
+        nodes
+    }
+
+    #[test]
+    fn cow_unique() {
+        // nobody else holds the nodes: everything happens in place
+        let mut list = from_slice(&[1, 2, 3, 4]);
+        let before = nodes(&list);
+        *list.head_mut().unwrap() = 10;
+        list.set(2, 30);
+        for x in list.iter_mut_cow() {
+            *x += 1;
+        }
+        assert_eq!(to_vec(&list), vec![11, 3, 31, 5]);
+        assert_eq!(nodes(&list), before);
+
+        assert_eq!(list.pop_front_unique(), Some(11));
+        assert_eq!(nodes(&list), before[1..]);
+        assert_eq!(List::<i32>::new().head_mut(), None);
+        assert_eq!(List::<i32>::new().pop_front_unique(), None);
+    }
+
+    #[test]
+    fn cow_shared() {
+        let original = from_slice(&[1, 2, 3, 4, 5]);
+        let old = nodes(&original);
+
+        // copies nodes 0..=2, shares 3 and 4
+        let mut list = original.clone();
+        list.set(2, 30);
+        let new = nodes(&list);
+        assert_eq!(to_vec(&list), vec![1, 2, 30, 4, 5]);
+        assert!(new[..3].iter().zip(&old).all(|(a, b)| a != b));
+        assert_eq!(new[3..], old[3..]);
+        assert_eq!(to_vec(&original), vec![1, 2, 3, 4, 5]);
+
+        // the copies are ours now: no further copying
+        *list.head_mut().unwrap() = 10;
+        list.set(1, 20);
+        assert_eq!(nodes(&list), new);
+
+        // only the nodes the iterator actually visits get copied
+        for x in list.iter_mut_cow().take(4) {
+            *x *= 2;
+        }
+        assert_eq!(to_vec(&list), vec![20, 40, 60, 8, 5]);
+        assert_eq!(nodes(&list)[..3], new[..3]);
+        assert_ne!(nodes(&list)[3], old[3]);
+        assert_eq!(nodes(&list)[4], old[4]);
+        assert_eq!(to_vec(&original), vec![1, 2, 3, 4, 5]);
+
+        // a shared head is cloned, the tail stays shared
+        let mut other = original.clone();
+        assert_eq!(other.pop_front_unique(), Some(1));
+        assert_eq!(nodes(&other), old[1..]);
+        assert_eq!(to_vec(&original), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "set index (is 2) should be < len (is 2)")]
+    fn set_out_of_bounds() {
+        from_slice(&[1, 2]).set(2, 0);
+    }
+
+    #[test]
+    fn shared_suffix() {
+        let base = from_slice(&[3, 4]);
+        let a = base.prepend(2).prepend(1);
+        let b = base.prepend(20);
+        assert!(shares(&a.shared_suffix(&b), &base));
+        assert!(shares(&b.shared_suffix(&a), &base));
+        assert!(shares(&a.shared_suffix(&a.tail()), &a.tail()));
+        assert!(shares(&a.shared_suffix(&a), &a));
+        // equal values aren't enough
+        assert!(from_slice(&[1, 2]).shared_suffix(&from_slice(&[1, 2])).is_empty());
+        assert!(a.shared_suffix(&List::new()).is_empty());
+    }
+
+    #[test]
+    fn sharing() {
+        let base = from_slice(&[3, 4]);
+        let a = base.prepend(2).prepend(1);
+        assert_eq!(a.sharing(), Sharing { unique: 2, shared: 2 });
+        assert_eq!(base.sharing(), Sharing { unique: 0, shared: 2 });
+        drop(base);
+        assert_eq!(a.sharing(), Sharing { unique: 4, shared: 0 });
+        let b = a.clone();
+        assert_eq!(b.sharing(), Sharing { unique: 0, shared: 4 });
+        assert_eq!(List::<i32>::new().sharing(), Sharing { unique: 0, shared: 0 });
+    }
+
+    #[test]
+    fn diff_patch() {
+        let base = from_slice(&[3, 4]);
+        let a = base.prepend(2).prepend(1);
+        let b = base.prepend(30).prepend(20).prepend(10);
+        let script = a.diff(&b);
+        assert_eq!(script, vec![
+            Edit::Remove(&1),
+            Edit::Remove(&2),
+            Edit::Insert(&10),
+            Edit::Insert(&20),
+            Edit::Insert(&30),
+        ]);
+        let patched = a.patch(&script);
+        assert_eq!(to_vec(&patched), to_vec(&b));
+        assert!(shares(&patched.drop(3), &base));
+
+        assert!(a.diff(&a).is_empty());
+        assert!(shares(&a.patch(&[]), &a));
+        assert_eq!(a.diff(&a.tail()), vec![Edit::Remove(&1)]);
+        assert!(a.patch(&a.diff(&List::new())).is_empty());
+    }
+
+    #[test]
+    fn long_lists() {
+        // none of these may recurse per node
+        let list = (0..200_000).fold(List::new(), |l, x| l.prepend(x));
+        let copy = list.map(|x| x + 1).filter(|x| x % 3 != 0).reverse();
+        assert_eq!(copy.len(), list.len() - list.len() / 3);
+        assert_eq!(list.append(&list).len(), 400_000);
+    }
 }
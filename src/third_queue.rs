@@ -0,0 +1,361 @@
+/*
+ * Persistent FIFO queue out of two persistent stacks (Okasaki's banker's
+ * queue).
+ *
+ * `front` holds the oldest elements in order, `back` the newest ones in
+ * *reverse* order, so both ends of the queue are the heads of a list:
+ * enqueue prepends to `back`, dequeue takes the tail of `front`. Whenever
+ * `back` grows longer than `front` it is reversed and appended to `front`
+ * ("rotation"), which keeps `front` from running dry.
+ *
+ * Every operation returns a new version and leaves the old one intact; the
+ * versions share all the nodes they can.
+ *
+ * Cost: a rotation of a queue with n elements copies n nodes, but it only
+ * happens after n/2 cheap enqueues. That alone would only make operations
+ * amortized O(1) as long as each version is used once: rotate the same old
+ * version over and over and you pay the O(n) copy every time. So `front` is
+ * a lazy `third_stream::Stream` and a rotation doesn't copy anything yet, it
+ * just suspends `front ++ reverse(back)`. The append is then forced one node
+ * per dequeue, and `reverse(back)` all at once when the append reaches it --
+ * by which point the |front| dequeues in between have paid for it. Forced
+ * nodes are memoized, so every version sharing a suspension shares the work
+ * too, and the bound holds however the versions are reused.
+ *
+ * That is why `front` isn't a `third::List` like `back`: a strict list would
+ * have to be built in full by whichever version rotates first, and every
+ * other version rotating the same queue would build its own. The stream's
+ * `'a` is only there because suspended rotations capture elements; queues
+ * of borrowed data work as long as the queue doesn't outlive the borrow.
+ *
+ * C. Okasaki, "Purely Functional Data Structures" (1998), section 6.3.2.
+ */
+
+use crate::third::{self, List};
+use crate::third_stream::{self, Stream};
+
+pub struct Queue<'a, T> {
+    front: Stream<'a, T>,
+    front_len: usize,
+    // reversed: the newest element is the head
+    back: List<T>,
+    back_len: usize,
+}
+
+impl<'a, T> Queue<'a, T> {
+    pub fn new() -> Self {
+        Queue { front: Stream::empty(), front_len: 0, back: List::new(), back_len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front_len + self.back_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The oldest element. `front` is only ever empty if `back` is, too.
+    pub fn peek(&self) -> Option<&T> {
+        self.front.head()
+    }
+}
+
+impl<'a, T: Clone + 'a> Queue<'a, T> {
+    pub fn enqueue(&self, elem: T) -> Queue<'a, T> {
+        Queue::balanced(
+            self.front.clone(),
+            self.front_len,
+            self.back.prepend(elem),
+            self.back_len + 1,
+        )
+    }
+
+    // Like `third::List::tail`: dequeuing an empty queue gives an empty queue.
+    pub fn dequeue(&self) -> Queue<'a, T> {
+        if self.is_empty() {
+            return Queue::new();
+        }
+        Queue::balanced(
+            self.front.tail(),
+            self.front_len - 1,
+            self.back.clone(),
+            self.back_len,
+        )
+    }
+
+    // Restores `back_len <= front_len`. Doesn't force anything.
+    fn balanced(front: Stream<'a, T>, front_len: usize, back: List<T>, back_len: usize) -> Self {
+        if back_len <= front_len {
+            return Queue { front, front_len, back, back_len };
+        }
+        Queue {
+            front: rotate(front, back),
+            front_len: front_len + back_len,
+            back: List::new(),
+            back_len: 0,
+        }
+    }
+}
+
+// `front ++ reverse(back)`, one node at a time.
+fn rotate<'a, T: Clone + 'a>(front: Stream<'a, T>, back: List<T>) -> Stream<'a, T> {
+    Stream::lazy(move || match front.head() {
+        Some(elem) => Some((elem.clone(), rotate(front.tail(), back))),
+        None => {
+            // `back` in one go: its newest element ends up last, and the
+            // oldest one is this node's element.
+            let mut rest = Stream::empty();
+            let mut oldest = None;
+            for elem in back.iter() {
+                if let Some(newer) = oldest.replace(elem.clone()) {
+                    rest = Stream::cons(newer, rest);
+                }
+            }
+            oldest.map(|elem| (elem, rest))
+        }
+    })
+}
+
+impl<T> Default for Queue<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for Queue<'_, T> {
+    fn clone(&self) -> Self {
+        Queue {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        }
+    }
+}
+
+// Oldest to newest. Walking `front` forces (and memoizes) it, same as
+// dequeuing all the way would.
+//
+// `back` can only be walked newest-first. Rather than collecting all of it,
+// the iterator remembers where every k-th node is (k ~ sqrt(len)) and then
+// collects one k-node segment at a time, last segment first: O(len) time
+// overall and O(sqrt(len)) extra space.
+pub struct Iter<'s, 'a, T> {
+    front: third_stream::Iter<'s, 'a, T>,
+    back: &'s List<T>,
+    back_len: usize,
+    // set up once `front` runs out
+    segments: Option<Segments<'s, T>>,
+}
+
+struct Segments<'a, T> {
+    // where each segment starts, newest segment first
+    starts: Vec<third::Iter<'a, T>>,
+    // the segment being yielded, newest first
+    current: Vec<&'a T>,
+    k: usize,
+}
+
+impl<'a, T> Segments<'a, T> {
+    fn new(back: &'a List<T>, len: usize) -> Self {
+        let k = ((len as f64).sqrt().ceil() as usize).max(1);
+        let mut starts = Vec::with_capacity(len.div_ceil(k));
+        let mut iter = back.iter();
+        for _ in (0..len).step_by(k) {
+            starts.push(iter.clone());
+            iter.nth(k - 1);
+        }
+        Segments { starts, current: Vec::with_capacity(k), k }
+    }
+}
+
+impl<'a, T> Queue<'a, T> {
+    pub fn iter(&self) -> Iter<'_, 'a, T> {
+        Iter {
+            front: self.front.iter(),
+            back: &self.back,
+            back_len: self.back_len,
+            segments: None,
+        }
+    }
+}
+
+impl<'s, T> Iterator for Iter<'s, '_, T> {
+    type Item = &'s T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(elem) = self.front.next() {
+            return Some(elem);
+        }
+        let segments = self.segments.get_or_insert_with(|| Segments::new(self.back, self.back_len));
+        loop {
+            if let Some(elem) = segments.current.pop() {
+                return Some(elem);
+            }
+            let start = segments.starts.pop()?;
+            segments.current.extend(start.take(segments.k));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Queue;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+
+    // Counts its clones, i.e. how many nodes the queue copies.
+    #[derive(Debug, PartialEq)]
+    struct Counted(i32);
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|n| n.set(n.get() + 1));
+            Counted(self.0)
+        }
+    }
+
+    fn clones() -> usize {
+        CLONES.with(Cell::get)
+    }
+
+    #[test]
+    fn basics() {
+        let q0 = Queue::new();
+        assert_eq!(q0.peek(), None);
+        assert!(q0.dequeue().is_empty());
+
+        let q1 = q0.enqueue(1);
+        let q2 = q1.enqueue(2);
+        let q3 = q2.enqueue(3);
+        assert_eq!(q3.peek(), Some(&1));
+        assert_eq!(q3.len(), 3);
+        assert_eq!(q3.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let q4 = q3.dequeue();
+        assert_eq!(q4.peek(), Some(&2));
+        let q5 = q4.enqueue(4).dequeue().dequeue();
+        assert_eq!(q5.iter().copied().collect::<Vec<_>>(), vec![4]);
+        assert!(q5.dequeue().is_empty());
+
+        // every old version still answers the same
+        assert!(q0.is_empty());
+        assert_eq!(q1.iter().copied().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(q2.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(q3.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(q4.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn back_never_outgrows_front() {
+        let mut q = Queue::new();
+        for i in 0..100 {
+            q = q.enqueue(i);
+            assert!(q.back_len <= q.front_len);
+            if i % 3 == 0 {
+                q = q.dequeue();
+                assert!(q.back_len <= q.front_len);
+            }
+        }
+    }
+
+    #[test]
+    fn iter_with_long_back() {
+        // lengths around the segment size, to catch off-by-ones
+        for n in 0..40 {
+            let mut q = Queue::new();
+            for i in 0..n {
+                q = q.enqueue(i);
+            }
+            assert!(q.iter().copied().eq(0..n));
+        }
+        let mut q = Queue::new();
+        for i in 0..2046 {
+            q = q.enqueue(i);
+        }
+        assert_eq!(q.back_len, 1023);
+        assert!(q.iter().copied().eq(0..2046));
+    }
+
+    #[test]
+    fn reused_old_version_copies_nothing() {
+        // 2 * 1023 enqueues leave front and back both 1023 long: the next
+        // dequeue rotates.
+        let mut q = Queue::new();
+        for i in 0..2046 {
+            q = q.enqueue(Counted(i));
+        }
+        assert_eq!((q.front_len, q.back_len), (1023, 1023));
+        // force `front` (earlier rotations) so only the new work is counted
+        assert_eq!(q.iter().count(), 2046);
+
+        // A strict rotation would copy 2045 nodes per call. A lazy one just
+        // suspends it; looking at the result forces one node.
+        let before = clones();
+        let versions: Vec<_> = (0..100).map(|_| q.dequeue()).collect();
+        assert_eq!(clones(), before);
+        for v in &versions {
+            assert_eq!(v.peek(), Some(&Counted(1)));
+        }
+        assert_eq!(clones(), before + 100);
+
+        // and dequeuing through a rotated version pays about one copy per
+        // element, however many times its prefix is walked
+        let before = clones();
+        let mut v = versions[0].clone();
+        for i in 1..2046 {
+            assert_eq!(v.peek(), Some(&Counted(i)));
+            v = v.dequeue();
+        }
+        assert!(v.is_empty());
+        assert!(clones() - before <= 2 * 2046);
+        let before = clones();
+        assert!(versions[0].iter().map(|c| c.0).eq(1..2046));
+        assert_eq!(clones(), before);
+    }
+
+    #[test]
+    fn borrowed_elems() {
+        let words: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let mut q = Queue::new();
+        for w in &words {
+            q = q.enqueue(w.as_str());
+        }
+        let q = q.dequeue();
+        assert_eq!(q.peek(), Some(&"1"));
+        assert!(q.iter().copied().eq(words[1..].iter().map(String::as_str)));
+    }
+
+    #[test]
+    fn many_versions() {
+        // Branch off random old versions and check each against a VecDeque
+        // model of the same history.
+        let mut versions = vec![(Queue::new(), VecDeque::new())];
+        let mut seed = 7u32;
+        let mut rand = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        for i in 0..2000 {
+            let (q, model) = &versions[rand(versions.len())];
+            let (q, mut model) = (q.clone(), model.clone());
+            let next = if rand(3) == 0 {
+                model.pop_front();
+                q.dequeue()
+            } else {
+                model.push_back(i);
+                q.enqueue(i)
+            };
+            assert_eq!(next.peek(), model.front());
+            assert_eq!(next.len(), model.len());
+            versions.push((next, model));
+        }
+        for (q, model) in &versions {
+            assert!(q.iter().eq(model.iter()));
+        }
+    }
+}
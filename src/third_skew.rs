@@ -0,0 +1,342 @@
+/*
+ * Persistent random-access list: Okasaki's skew-binary random-access list.
+ *
+ * Same interface as `third::List` (prepend/head/tail in O(1), old versions
+ * stay valid) but indexing is O(log n) instead of O(n).
+ *
+ * The elements are kept in a list ("spine") of complete binary trees, stored
+ * in preorder: a tree's root is its first element, then its left subtree,
+ * then its right one. Tree sizes are of the form 2^k - 1 and strictly
+ * increase along the spine, except that the first two may be equal -- the
+ * "skew binary" number system, in which adding one never carries more than
+ * once:
+ *
+ * - prepend: if the first two trees have the same size w, replace them by
+ *   one tree of size 2w + 1 with the new element as root; otherwise put a
+ *   leaf (size 1) in front.
+ * - tail: a leaf just goes away; a tree loses its root and its two subtrees
+ *   go back onto the spine.
+ *
+ * The spine is a plain `third::List`, so it shares its suffixes the same
+ * way, and the trees are shared through `Rc`: `set` only copies the path to
+ * the changed element and the spine in front of its tree.
+ *
+ * C. Okasaki, "Purely Functional Random-Access Lists" (1995).
+ */
+
+use std::rc::Rc;
+
+use crate::third;
+
+pub struct List<T> {
+    spine: third::List<Digit<T>>,
+}
+
+// A tree plus its size (always 2^k - 1).
+struct Digit<T> {
+    size: usize,
+    tree: Rc<Tree<T>>,
+}
+
+enum Tree<T> {
+    Leaf(T),
+    Node(T, Rc<Tree<T>>, Rc<Tree<T>>),
+}
+
+impl<T> Clone for Digit<T> {
+    fn clone(&self) -> Self {
+        Digit { size: self.size, tree: self.tree.clone() }
+    }
+}
+
+impl<T> Tree<T> {
+    fn elem(&self) -> &T {
+        match self {
+            Tree::Leaf(elem) | Tree::Node(elem, _, _) => elem,
+        }
+    }
+
+    // `i`-th element in preorder of a tree with `size` elements.
+    fn get(mut tree: &Tree<T>, mut size: usize, mut i: usize) -> &T {
+        loop {
+            match tree {
+                Tree::Node(_, left, right) if i > 0 => {
+                    size /= 2;
+                    if i <= size {
+                        tree = left;
+                        i -= 1;
+                    } else {
+                        tree = right;
+                        i -= 1 + size;
+                    }
+                }
+                _ => return tree.elem(),
+            }
+        }
+    }
+
+    // Copies the path down to the `i`-th element, shares everything else.
+    // (Recursion is fine here: trees are only log n deep.)
+    fn set(tree: &Rc<Tree<T>>, size: usize, i: usize, elem: T) -> Rc<Tree<T>>
+    where
+        T: Clone,
+    {
+        Rc::new(match &**tree {
+            Tree::Leaf(_) => Tree::Leaf(elem),
+            Tree::Node(_, left, right) if i == 0 => Tree::Node(elem, left.clone(), right.clone()),
+            Tree::Node(root, left, right) => {
+                let half = size / 2;
+                if i <= half {
+                    Tree::Node(root.clone(), Tree::set(left, half, i - 1, elem), right.clone())
+                } else {
+                    Tree::Node(root.clone(), left.clone(), Tree::set(right, half, i - 1 - half, elem))
+                }
+            }
+        })
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { spine: third::List::new() }
+    }
+
+    pub fn prepend(&self, elem: T) -> List<T> {
+        let mut digits = self.spine.iter();
+        if let (Some(a), Some(b)) = (digits.next(), digits.next()) {
+            if a.size == b.size {
+                let tree = Rc::new(Tree::Node(elem, a.tree.clone(), b.tree.clone()));
+                return List { spine: self.spine.drop(2).prepend(Digit { size: 2 * a.size + 1, tree }) };
+            }
+        }
+        List { spine: self.spine.prepend(Digit { size: 1, tree: Rc::new(Tree::Leaf(elem)) }) }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.spine.head().map(|digit| digit.tree.elem())
+    }
+
+    // Like `third::List::tail`: the tail of an empty list is empty.
+    pub fn tail(&self) -> List<T> {
+        let Some(digit) = self.spine.head() else {
+            return List::new();
+        };
+        match &*digit.tree {
+            Tree::Leaf(_) => List { spine: self.spine.tail() },
+            Tree::Node(_, left, right) => {
+                let size = digit.size / 2;
+                let spine = self.spine.tail()
+                    .prepend(Digit { size, tree: right.clone() })
+                    .prepend(Digit { size, tree: left.clone() });
+                List { spine }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spine.is_empty()
+    }
+
+    // O(log n): there are at most log n trees.
+    pub fn len(&self) -> usize {
+        self.spine.iter().map(|digit| digit.size).sum()
+    }
+
+    // O(log n): find the tree, then walk down it.
+    pub fn get(&self, mut i: usize) -> Option<&T> {
+        for digit in self.spine.iter() {
+            if i < digit.size {
+                return Some(Tree::get(&digit.tree, digit.size, i));
+            }
+            i -= digit.size;
+        }
+        None
+    }
+
+    // A new version with the `i`-th element replaced. Copies the O(log n)
+    // spine entries in front of the affected tree and the path inside it;
+    // shares the rest of the spine and every other subtree.
+    //
+    // Panics if `i >= len`.
+    pub fn set(&self, i: usize, elem: T) -> List<T>
+    where
+        T: Clone,
+    {
+        let mut prefix = vec![];
+        let mut rest = self.spine.clone();
+        let mut j = i;
+        loop {
+            let Some(digit) = rest.head() else {
+                panic!("set index (is {}) should be < len (is {})", i, self.len());
+            };
+            if j < digit.size {
+                break;
+            }
+            j -= digit.size;
+            prefix.push(digit.clone());
+            rest = rest.tail();
+        }
+        let digit = rest.head().unwrap();
+        let changed = Digit { size: digit.size, tree: Tree::set(&digit.tree, digit.size, j, elem) };
+        let spine = prefix.into_iter().rev().fold(rest.tail().prepend(changed), |spine, d| spine.prepend(d));
+        List { spine }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List { spine: self.spine.clone() }
+    }
+}
+
+// Front to back: every tree in preorder, trees in spine order. Yields `&T`
+// just like `third::Iter`.
+pub struct Iter<'a, T> {
+    digits: third::Iter<'a, Digit<T>>,
+    // subtrees still to visit in the current tree (at most log n of them)
+    stack: Vec<&'a Tree<T>>,
+}
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { digits: self.spine.iter(), stack: vec![] }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop() {
+                Some(Tree::Leaf(elem)) => return Some(elem),
+                Some(Tree::Node(elem, left, right)) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                    return Some(elem);
+                }
+                None => self.stack.push(&self.digits.next()?.tree),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::rc::Rc;
+
+    fn from_slice<T: Clone>(v: &[T]) -> List<T> {
+        v.iter().rev().fold(List::new(), |l, x| l.prepend(x.clone()))
+    }
+
+    fn to_vec<T: Clone>(l: &List<T>) -> Vec<T> {
+        l.iter().cloned().collect()
+    }
+
+    fn sizes<T>(l: &List<T>) -> Vec<usize> {
+        l.spine.iter().map(|d| d.size).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let l0 = List::new();
+        assert_eq!(l0.head(), None);
+        assert!(l0.tail().is_empty());
+
+        let l1 = l0.prepend(1);
+        let l3 = l1.prepend(2).prepend(3);
+        assert_eq!(l3.head(), Some(&3));
+        assert_eq!(l3.tail().head(), Some(&2));
+        assert_eq!(l3.tail().tail().tail().head(), None);
+        assert_eq!(l1.head(), Some(&1));
+        assert!(l0.is_empty());
+        assert_eq!(to_vec(&l3), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn skew_binary_shape() {
+        // counting in skew binary: each prepend carries at most once
+        let mut l = List::new();
+        let mut shapes = vec![];
+        for i in 0..7 {
+            l = l.prepend(i);
+            shapes.push(sizes(&l));
+        }
+        assert_eq!(shapes, vec![
+            vec![1],
+            vec![1, 1],
+            vec![3],
+            vec![1, 3],
+            vec![1, 1, 3],
+            vec![3, 3],
+            vec![7],
+        ]);
+        // tail is the exact inverse of prepend
+        for expected in shapes.iter().rev().skip(1) {
+            l = l.tail();
+            assert_eq!(&sizes(&l), expected);
+        }
+    }
+
+    #[test]
+    fn get_and_set() {
+        let n = 1000;
+        let v: Vec<usize> = (0..n).collect();
+        let list = from_slice(&v);
+        assert_eq!(list.len(), n);
+        for i in 0..n {
+            assert_eq!(list.get(i), Some(&i));
+        }
+        assert_eq!(list.get(n), None);
+        assert_eq!(to_vec(&list), v);
+
+        let mut model = v.clone();
+        let mut updated = list.clone();
+        for i in (0..n).step_by(7) {
+            updated = updated.set(i, i * 100);
+            model[i] = i * 100;
+        }
+        assert_eq!(to_vec(&updated), model);
+        // the original is untouched
+        assert_eq!(to_vec(&list), v);
+    }
+
+    #[test]
+    fn set_shares() {
+        let list = from_slice(&(0..100).collect::<Vec<_>>());
+        let changed = list.set(0, 42);
+        // only the first tree is rebuilt, the rest of the spine is shared
+        let old: Vec<_> = list.spine.iter().map(|d| d.tree.clone()).collect();
+        let new: Vec<_> = changed.spine.iter().map(|d| d.tree.clone()).collect();
+        assert!(!Rc::ptr_eq(&old[0], &new[0]));
+        assert!(old[1..].iter().zip(&new[1..]).all(|(a, b)| Rc::ptr_eq(a, b)));
+
+        // in the last tree: the spine in front of it is copied
+        let last = list.set(99, 0);
+        assert_eq!(last.get(99), Some(&0));
+        assert_eq!(last.get(98), Some(&98));
+    }
+
+    #[test]
+    #[should_panic(expected = "set index (is 3) should be < len (is 3)")]
+    fn set_out_of_bounds() {
+        from_slice(&[1, 2, 3]).set(3, 0);
+    }
+
+    #[test]
+    fn long_list() {
+        let mut list = List::new();
+        for i in 0..200_000 {
+            list = list.prepend(i);
+        }
+        assert_eq!(list.get(0), Some(&199_999));
+        assert_eq!(list.get(199_999), Some(&0));
+        assert_eq!(list.iter().count(), 200_000);
+    }
+}
@@ -0,0 +1,310 @@
+/*
+ * Lazy (and possibly infinite) persistent lists.
+ *
+ * Same shape as `third::List` -- nodes shared through `Rc` -- but a node's
+ * contents are only computed when somebody first looks at them. Each link
+ * points at a `Lazy` cell holding either a not-yet-run closure (the
+ * "thunk") or its memoized result: `None` for the end of the stream, or the
+ * element plus the (again lazy) rest. The thunk runs at most once; every
+ * version sharing the cell sees the same result.
+ *
+ * Because the rest is only built on demand, a stream can describe an
+ * infinite sequence (`iterate`, `repeat`, `cycle`) as long as you only ever
+ * force a finite prefix of it.
+ *
+ * Thunks are closures, so they may borrow; `'a` is how long they can. A
+ * stream of `&str`s, or one whose thunks capture a local, just can't outlive
+ * what it borrows.
+ */
+
+use std::cell::{Cell, OnceCell};
+use std::rc::Rc;
+
+use crate::third::List;
+
+pub struct Stream<'a, T> {
+    // only None while being dropped
+    head: Option<Rc<Lazy<'a, T>>>,
+}
+
+type Thunk<'a, T> = Box<dyn FnOnce() -> Option<(T, Stream<'a, T>)> + 'a>;
+
+struct Lazy<'a, T> {
+    value: OnceCell<Option<(T, Stream<'a, T>)>>,
+    thunk: Cell<Option<Thunk<'a, T>>>,
+}
+
+impl<'a, T> Stream<'a, T> {
+    fn forced(value: Option<(T, Stream<'a, T>)>) -> Self {
+        Stream { head: Some(Rc::new(Lazy { value: OnceCell::from(value), thunk: Cell::new(None) })) }
+    }
+
+    pub fn empty() -> Self {
+        Stream::forced(None)
+    }
+
+    pub fn cons(elem: T, rest: Stream<'a, T>) -> Self {
+        Stream::forced(Some((elem, rest)))
+    }
+
+    // `f` produces the first element and the rest, or None for "empty". It
+    // is called (once) the first time the stream is looked at.
+    pub fn lazy<F>(f: F) -> Self
+    where
+        F: FnOnce() -> Option<(T, Stream<'a, T>)> + 'a,
+    {
+        Stream {
+            head: Some(Rc::new(Lazy { value: OnceCell::new(), thunk: Cell::new(Some(Box::new(f))) })),
+        }
+    }
+
+    // Runs the thunk if it hasn't run yet.
+    fn force(&self) -> Option<&(T, Stream<'a, T>)> {
+        let lazy = self.head.as_ref().unwrap();
+        lazy.value
+            .get_or_init(|| {
+                let thunk = lazy.thunk.take().expect("stream forced from within its own thunk");
+                thunk()
+            })
+            .as_ref()
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.force().map(|(elem, _)| elem)
+    }
+
+    // Like `third::List::tail`: the tail of an empty stream is empty.
+    pub fn tail(&self) -> Stream<'a, T> {
+        self.force().map_or_else(Stream::empty, |(_, rest)| rest.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.force().is_none()
+    }
+
+    // Is the first node computed already? Doesn't force anything.
+    pub fn is_forced(&self) -> bool {
+        self.head.as_ref().unwrap().value.get().is_some()
+    }
+}
+
+impl<'a, T: 'a> Stream<'a, T> {
+    // `seed, f(seed), f(f(seed)), ...`
+    pub fn iterate<F>(seed: T, f: F) -> Self
+    where
+        F: Fn(&T) -> T + 'a,
+    {
+        Stream::unfold(seed, move |x| {
+            let next = f(&x);
+            Some((x, next))
+        })
+    }
+
+    // Runs `f` on the state to get the next element and state, until it
+    // returns None.
+    pub fn unfold<S, F>(state: S, f: F) -> Self
+    where
+        S: 'a,
+        F: Fn(S) -> Option<(T, S)> + 'a,
+    {
+        unfold_rc(state, Rc::new(f))
+    }
+
+    // `x, x, x, ...`
+    pub fn repeat(x: T) -> Self
+    where
+        T: Clone,
+    {
+        Stream::unfold((), move |()| Some((x.clone(), ())))
+    }
+
+    // This stream's elements over and over; empty if this one is empty.
+    pub fn cycle(&self) -> Self
+    where
+        T: Clone,
+    {
+        let start = self.clone();
+        Stream::unfold(self.clone(), move |cur| {
+            let cur = if cur.is_empty() { start.clone() } else { cur };
+            cur.head().cloned().map(|elem| (elem, cur.tail()))
+        })
+    }
+
+    // The first `n` elements, lazily. Doesn't force anything by itself.
+    pub fn take(&self, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        Stream::unfold((self.clone(), n), |(cur, n)| {
+            if n == 0 {
+                return None;
+            }
+            cur.head().cloned().map(|elem| (elem, (cur.tail(), n - 1)))
+        })
+    }
+
+    pub fn map<U: 'a, F>(&self, f: F) -> Stream<'a, U>
+    where
+        F: Fn(&T) -> U + 'a,
+    {
+        Stream::unfold(self.clone(), move |cur| {
+            cur.head().map(&f).map(|elem| (elem, cur.tail()))
+        })
+    }
+
+    // Shares nothing with `list` (different node type), but is lazy too.
+    pub fn from_list(list: &List<T>) -> Self
+    where
+        T: Clone,
+    {
+        Stream::unfold(list.clone(), |cur| cur.head().cloned().map(|elem| (elem, cur.tail())))
+    }
+}
+
+fn unfold_rc<'a, T: 'a, S: 'a>(
+    state: S,
+    f: Rc<dyn Fn(S) -> Option<(T, S)> + 'a>,
+) -> Stream<'a, T> {
+    Stream::lazy(move || {
+        let next = f(state)?;
+        Some((next.0, unfold_rc(next.1, f)))
+    })
+}
+
+impl<'a, T> Stream<'a, T> {
+    // Forces the whole stream into a strict list. Never returns for an
+    // infinite stream: `take` first.
+    pub fn to_list(&self) -> List<T>
+    where
+        T: Clone,
+    {
+        let elems: Vec<T> = self.iter().cloned().collect();
+        elems.into_iter().rev().fold(List::new(), |list, elem| list.prepend(elem))
+    }
+}
+
+impl<'a, T> Clone for Stream<'a, T> {
+    fn clone(&self) -> Self {
+        Stream { head: self.head.clone() }
+    }
+}
+
+// Same as third's: only free nodes nobody else holds, and loop instead of
+// recursing through `rest`. (Unforced thunks may hold streams of their own;
+// those get dropped the same way.)
+impl<'a, T> Drop for Stream<'a, T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(lazy) = head {
+            head = match Rc::try_unwrap(lazy) {
+                Ok(lazy) => lazy.value.into_inner().flatten().and_then(|(_, mut rest)| rest.head.take()),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+// Forces the stream one element at a time as it goes.
+pub struct Iter<'s, 'a, T> {
+    next: Option<&'s Stream<'a, T>>,
+}
+
+impl<'a, T> Stream<'a, T> {
+    pub fn iter(&self) -> Iter<'_, 'a, T> {
+        Iter { next: Some(self) }
+    }
+}
+
+impl<'s, 'a, T> Iterator for Iter<'s, 'a, T> {
+    type Item = &'s T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (elem, rest) = self.next.take()?.force()?;
+        self.next = Some(rest);
+        Some(elem)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Stream;
+    use crate::third::List;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn prefix<T: Clone>(s: &Stream<T>, n: usize) -> Vec<T> {
+        s.iter().take(n).cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let s = Stream::cons(1, Stream::cons(2, Stream::empty()));
+        assert_eq!(s.head(), Some(&1));
+        assert_eq!(s.tail().head(), Some(&2));
+        assert!(s.tail().tail().is_empty());
+        assert!(s.tail().tail().tail().is_empty());
+        assert_eq!(prefix(&s, 10), vec![1, 2]);
+    }
+
+    #[test]
+    fn infinite() {
+        let nats = Stream::iterate(0u64, |x| x + 1);
+        assert_eq!(prefix(&nats, 5), vec![0, 1, 2, 3, 4]);
+        assert_eq!(nats.take(3).to_list().iter().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+        let squares = nats.map(|x| x * x);
+        assert_eq!(prefix(&squares, 5), vec![0, 1, 4, 9, 16]);
+        assert_eq!(prefix(&Stream::repeat('x'), 3), vec!['x', 'x', 'x']);
+
+        let fib = Stream::unfold((0u64, 1u64), |(a, b)| Some((a, (b, a + b))));
+        assert_eq!(fib.iter().nth(50), Some(&12_586_269_025));
+    }
+
+    #[test]
+    fn unfold_finite() {
+        let collatz = Stream::unfold(6u32, |n| match n {
+            0 => None,
+            1 => Some((1, 0)),
+            n if n % 2 == 0 => Some((n, n / 2)),
+            n => Some((n, 3 * n + 1)),
+        });
+        assert_eq!(prefix(&collatz, 100), vec![6, 3, 10, 5, 16, 8, 4, 2, 1]);
+    }
+
+    #[test]
+    fn cycle() {
+        let list = List::new().prepend(3).prepend(2).prepend(1);
+        let s = Stream::from_list(&list).cycle();
+        assert_eq!(prefix(&s, 7), vec![1, 2, 3, 1, 2, 3, 1]);
+        assert!(Stream::<i32>::empty().cycle().is_empty());
+        assert_eq!(prefix(&Stream::from_list(&list).take(2).cycle(), 5), vec![1, 2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn lazy_and_memoized() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let s = Stream::iterate(0, move |x| {
+            counter.set(counter.get() + 1);
+            x + 1
+        });
+        // nothing runs until somebody looks
+        let taken = s.take(1000);
+        assert_eq!(calls.get(), 0);
+        assert!(!s.is_forced());
+
+        assert_eq!(prefix(&taken, 5), vec![0, 1, 2, 3, 4]);
+        let after_first = calls.get();
+        assert!(after_first <= 5);
+        // the same prefix again, from another version: all memoized
+        assert_eq!(prefix(&s, 5), vec![0, 1, 2, 3, 4]);
+        assert_eq!(prefix(&s.tail().tail(), 3), vec![2, 3, 4]);
+        assert_eq!(calls.get(), after_first);
+    }
+
+    #[test]
+    fn long_forced_stream_drops() {
+        let s = Stream::iterate(0u32, |x| x + 1);
+        assert_eq!(s.iter().nth(1_000_000), Some(&1_000_000));
+        // a million forced nodes hanging off `s`
+        drop(s);
+    }
+}
@@ -0,0 +1,176 @@
+// Same persistent list as `third`, with `Arc` in place of `Rc`.
+//
+// Rc bumps its reference count with plain (non-atomic) increments, so two
+// threads cloning the same Rc at once could lose an update: Rc is neither
+// Send nor Sync, and neither is anything built from it. Arc does the same
+// thing with atomic instructions, which costs a bit more but makes
+// `List<T>: Send + Sync` whenever `T: Send + Sync` (auto traits, see third.rs).
+use std::sync::Arc;
+
+pub struct List<T> {
+    head: Link<T>
+}
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None }
+    }
+
+    pub fn prepend(&self, elem: T) -> List<T> {
+        List { head: Some(Arc::new(Node {
+            elem,
+            next: self.head.clone()
+        })) }
+    }
+
+    pub fn tail(&self) -> List<T> {
+        List { head: self.head.as_ref().and_then(|n| n.next.clone()) }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|n| &n.elem)
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_deref() }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            // Not `try_unwrap`: if two threads drop the last two lists sharing
+            // a node, both can see a count of 2 and get Err, and then the
+            // last Arc frees the rest of the chain recursively. into_inner
+            // hands the node to exactly one of them, which keeps unwrapping.
+            if let Some(mut node) = Arc::into_inner(node) {
+                head = node.next.take();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn iter() {
+        let list = List::new().prepend(1).prepend(2);
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn basics() {
+        let l1 = List::new();
+        assert_eq!(l1.head(), None);
+
+        let l2 = l1.prepend(1).prepend(2).prepend(3);
+        assert_eq!(l1.head(), None);
+        assert_eq!(l2.head(), Some(&3));
+
+        assert_eq!(l2.tail().head(), Some(&2));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<List<i32>>();
+        is_send_sync::<super::Iter<'static, i32>>();
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let base = (0..100).fold(List::new(), |l, i| l.prepend(i));
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|t| {
+                    let base = &base;
+                    s.spawn(move || {
+                        // every thread extends the same history its own way
+                        let mine = base.prepend(1000 + t);
+                        assert_eq!(mine.head(), Some(&(1000 + t)));
+                        assert_eq!(mine.tail().head(), Some(&99));
+                        mine.iter().skip(1).sum::<i32>()
+                    })
+                })
+                .collect();
+            for h in handles {
+                assert_eq!(h.join().unwrap(), (0..100).sum::<i32>());
+            }
+        });
+        assert_eq!(base.iter().count(), 100);
+    }
+
+    #[test]
+    fn dropped_on_other_threads() {
+        let base = (0..10_000).fold(List::new(), |l, i| l.prepend(i));
+        thread::scope(|s| {
+            for t in 0..4 {
+                // each thread owns a version sharing all of `base`'s nodes
+                // and drops it there, racing with the others.
+                let version = base.tail().prepend(t);
+                s.spawn(move || drop(version));
+            }
+        });
+        assert_eq!(base.head(), Some(&9_999));
+        assert_eq!(base.iter().count(), 10_000);
+        // and the last owner frees the (long) rest without blowing the stack
+        drop(base);
+    }
+
+    #[test]
+    fn last_two_dropped_at_once() {
+        let base = (0..100_000).fold(List::new(), |l, i| l.prepend(i));
+        // two lists sharing all 100k nodes, and nobody else holding them
+        let versions = [base.prepend(-1), base.prepend(-2)];
+        drop(base);
+        let barrier = Barrier::new(2);
+        thread::scope(|s| {
+            for version in versions {
+                let barrier = &barrier;
+                s.spawn(move || {
+                    barrier.wait();
+                    drop(version);
+                });
+            }
+        });
+    }
+}
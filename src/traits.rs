@@ -0,0 +1,296 @@
+/*
+ * Common interfaces over the lists, so code can be generic over "some stack"
+ * or "some queue" instead of over `push` vs `push_front` vs `prepend`.
+ *
+ * The traits only cover moving elements in and out: peeking differs too much
+ * between the lists (`i32` by value, `&T`, `Ref<T>`, a copy for the
+ * concurrent ones) to share a signature.
+ */
+
+use crate::{
+    fifth, fifth_noref, fifth_sync, first, fourth, fourth_slab, second, second_arena, second_sync,
+    sixth, third, third_skew, third_sync, unrolled,
+};
+
+// Last in, first out.
+pub trait Stack<T> {
+    fn push(&mut self, elem: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+// First in, first out.
+pub trait Queue<T> {
+    fn enqueue(&mut self, elem: T);
+    fn dequeue(&mut self) -> Option<T>;
+}
+
+// Both ends.
+pub trait Deque<T> {
+    fn push_front(&mut self, elem: T);
+    fn push_back(&mut self, elem: T);
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+}
+
+// Immutable stack: every "modification" returns a new version, old versions
+// stay valid.
+pub trait PersistentStack<T>: Sized {
+    fn empty() -> Self;
+    fn prepend(&self, elem: T) -> Self;
+    fn head(&self) -> Option<&T>;
+    fn tail(&self) -> Self;
+}
+
+impl Stack<i32> for first::List {
+    fn push(&mut self, elem: i32) { first::List::push(self, elem) }
+    fn pop(&mut self) -> Option<i32> { first::List::pop(self) }
+}
+
+impl<T> Stack<T> for second::List<T> {
+    fn push(&mut self, elem: T) { second::List::push(self, elem) }
+    fn pop(&mut self) -> Option<T> { second::List::pop(self) }
+}
+
+impl<T> Stack<T> for second_arena::List<T> {
+    fn push(&mut self, elem: T) { second_arena::List::push(self, elem) }
+    fn pop(&mut self) -> Option<T> { second_arena::List::pop(self) }
+}
+
+impl<T> Stack<T> for second_sync::List<T> {
+    fn push(&mut self, elem: T) { second_sync::List::push(self, elem) }
+    fn pop(&mut self) -> Option<T> { second_sync::List::pop(self) }
+}
+
+impl<T> PersistentStack<T> for third::List<T> {
+    fn empty() -> Self { third::List::new() }
+    fn prepend(&self, elem: T) -> Self { third::List::prepend(self, elem) }
+    fn head(&self) -> Option<&T> { third::List::head(self) }
+    fn tail(&self) -> Self { third::List::tail(self) }
+}
+
+impl<T> PersistentStack<T> for third_sync::List<T> {
+    fn empty() -> Self { third_sync::List::new() }
+    fn prepend(&self, elem: T) -> Self { third_sync::List::prepend(self, elem) }
+    fn head(&self) -> Option<&T> { third_sync::List::head(self) }
+    fn tail(&self) -> Self { third_sync::List::tail(self) }
+}
+
+impl<T> PersistentStack<T> for third_skew::List<T> {
+    fn empty() -> Self { third_skew::List::new() }
+    fn prepend(&self, elem: T) -> Self { third_skew::List::prepend(self, elem) }
+    fn head(&self) -> Option<&T> { third_skew::List::head(self) }
+    fn tail(&self) -> Self { third_skew::List::tail(self) }
+}
+
+impl<T> Stack<T> for fourth::List<T> {
+    fn push(&mut self, elem: T) { fourth::List::push_front(self, elem) }
+    fn pop(&mut self) -> Option<T> { fourth::List::pop_front(self) }
+}
+
+impl<T> Queue<T> for fourth::List<T> {
+    fn enqueue(&mut self, elem: T) { fourth::List::push_back(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { fourth::List::pop_front(self) }
+}
+
+impl<T> Deque<T> for fourth::List<T> {
+    fn push_front(&mut self, elem: T) { fourth::List::push_front(self, elem) }
+    fn push_back(&mut self, elem: T) { fourth::List::push_back(self, elem) }
+    fn pop_front(&mut self) -> Option<T> { fourth::List::pop_front(self) }
+    fn pop_back(&mut self) -> Option<T> { fourth::List::pop_back(self) }
+}
+
+impl<T> Stack<T> for fourth_slab::List<T> {
+    fn push(&mut self, elem: T) { fourth_slab::List::push_front(self, elem); }
+    fn pop(&mut self) -> Option<T> { fourth_slab::List::pop_front(self) }
+}
+
+impl<T> Queue<T> for fourth_slab::List<T> {
+    fn enqueue(&mut self, elem: T) { fourth_slab::List::push_back(self, elem); }
+    fn dequeue(&mut self) -> Option<T> { fourth_slab::List::pop_front(self) }
+}
+
+impl<T> Deque<T> for fourth_slab::List<T> {
+    fn push_front(&mut self, elem: T) { fourth_slab::List::push_front(self, elem); }
+    fn push_back(&mut self, elem: T) { fourth_slab::List::push_back(self, elem); }
+    fn pop_front(&mut self) -> Option<T> { fourth_slab::List::pop_front(self) }
+    fn pop_back(&mut self) -> Option<T> { fourth_slab::List::pop_back(self) }
+}
+
+impl<T> Queue<T> for fifth::List<T> {
+    fn enqueue(&mut self, elem: T) { fifth::List::push(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { fifth::List::pop(self) }
+}
+
+impl<T> Queue<T> for fifth_noref::List<T> {
+    fn enqueue(&mut self, elem: T) { fifth_noref::List::push(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { fifth_noref::List::pop(self) }
+}
+
+impl<T> Queue<T> for fifth_sync::List<T> {
+    fn enqueue(&mut self, elem: T) { fifth_sync::List::push(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { fifth_sync::List::pop(self) }
+}
+
+impl<T> Stack<T> for sixth::List<T> {
+    fn push(&mut self, elem: T) { sixth::List::push_front(self, elem) }
+    fn pop(&mut self) -> Option<T> { sixth::List::pop_front(self) }
+}
+
+impl<T> Queue<T> for sixth::List<T> {
+    fn enqueue(&mut self, elem: T) { sixth::List::push_back(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { sixth::List::pop_front(self) }
+}
+
+impl<T> Deque<T> for sixth::List<T> {
+    fn push_front(&mut self, elem: T) { sixth::List::push_front(self, elem) }
+    fn push_back(&mut self, elem: T) { sixth::List::push_back(self, elem) }
+    fn pop_front(&mut self) -> Option<T> { sixth::List::pop_front(self) }
+    fn pop_back(&mut self) -> Option<T> { sixth::List::pop_back(self) }
+}
+
+impl<T, const N: usize> Stack<T> for unrolled::List<T, N> {
+    fn push(&mut self, elem: T) { unrolled::List::push_front(self, elem) }
+    fn pop(&mut self) -> Option<T> { unrolled::List::pop_front(self) }
+}
+
+impl<T, const N: usize> Queue<T> for unrolled::List<T, N> {
+    fn enqueue(&mut self, elem: T) { unrolled::List::push_back(self, elem) }
+    fn dequeue(&mut self) -> Option<T> { unrolled::List::pop_front(self) }
+}
+
+impl<T, const N: usize> Deque<T> for unrolled::List<T, N> {
+    fn push_front(&mut self, elem: T) { unrolled::List::push_front(self, elem) }
+    fn push_back(&mut self, elem: T) { unrolled::List::push_back(self, elem) }
+    fn pop_front(&mut self) -> Option<T> { unrolled::List::pop_front(self) }
+    fn pop_back(&mut self) -> Option<T> { unrolled::List::pop_back(self) }
+}
+
+// Conformance suite: the same scenarios against every implementation.
+#[cfg(test)]
+mod test {
+    use super::{Deque, PersistentStack, Queue, Stack};
+    use crate::{
+        fifth, fifth_noref, fifth_sync, first, fourth, fourth_slab, second, second_arena, second_sync,
+        sixth, third, third_skew, third_sync, unrolled,
+    };
+
+    fn check_stack<S: Stack<i32>>(mut s: S) {
+        assert_eq!(s.pop(), None);
+        s.push(1);
+        s.push(2);
+        s.push(3);
+        assert_eq!(s.pop(), Some(3));
+        assert_eq!(s.pop(), Some(2));
+        s.push(4);
+        assert_eq!(s.pop(), Some(4));
+        assert_eq!(s.pop(), Some(1));
+        assert_eq!(s.pop(), None);
+        // usable again after running empty
+        s.push(5);
+        assert_eq!(s.pop(), Some(5));
+        assert_eq!(s.pop(), None);
+        // and droppable with leftovers
+        for i in 0..100 {
+            s.push(i);
+        }
+    }
+
+    fn check_queue<Q: Queue<i32>>(mut q: Q) {
+        assert_eq!(q.dequeue(), None);
+        q.enqueue(1);
+        q.enqueue(2);
+        q.enqueue(3);
+        assert_eq!(q.dequeue(), Some(1));
+        assert_eq!(q.dequeue(), Some(2));
+        q.enqueue(4);
+        assert_eq!(q.dequeue(), Some(3));
+        assert_eq!(q.dequeue(), Some(4));
+        assert_eq!(q.dequeue(), None);
+        q.enqueue(5);
+        assert_eq!(q.dequeue(), Some(5));
+        assert_eq!(q.dequeue(), None);
+        for i in 0..100 {
+            q.enqueue(i);
+        }
+    }
+
+    fn check_deque<D: Deque<i32>>(mut d: D) {
+        assert_eq!(d.pop_front(), None);
+        assert_eq!(d.pop_back(), None);
+        d.push_front(2);
+        d.push_back(3);
+        d.push_front(1);
+        d.push_back(4);
+        assert_eq!(d.pop_front(), Some(1));
+        assert_eq!(d.pop_back(), Some(4));
+        assert_eq!(d.pop_back(), Some(3));
+        assert_eq!(d.pop_back(), Some(2));
+        assert_eq!(d.pop_front(), None);
+        assert_eq!(d.pop_back(), None);
+        d.push_back(5);
+        assert_eq!(d.pop_front(), Some(5));
+        d.push_front(6);
+        assert_eq!(d.pop_back(), Some(6));
+        for i in 0..100 {
+            d.push_back(i);
+        }
+    }
+
+    fn check_persistent_stack<P: PersistentStack<i32>>() {
+        let empty = P::empty();
+        assert_eq!(empty.head(), None);
+        assert_eq!(empty.tail().head(), None);
+
+        let l1 = empty.prepend(1);
+        let l2 = l1.prepend(2);
+        let l3a = l2.prepend(3);
+        let l3b = l2.prepend(30);
+        // old versions unaffected
+        assert_eq!(empty.head(), None);
+        assert_eq!(l1.head(), Some(&1));
+        assert_eq!(l2.head(), Some(&2));
+        assert_eq!(l3a.head(), Some(&3));
+        assert_eq!(l3b.head(), Some(&30));
+        assert_eq!(l3a.tail().head(), Some(&2));
+        assert_eq!(l3b.tail().tail().head(), Some(&1));
+        assert_eq!(l3b.tail().tail().tail().head(), None);
+    }
+
+    #[test]
+    fn stacks() {
+        check_stack(first::List::new());
+        check_stack(second::List::new());
+        check_stack(second_arena::List::new());
+        check_stack(second_sync::List::new());
+        check_stack(fourth::List::new());
+        check_stack(fourth_slab::List::new());
+        check_stack(sixth::List::new());
+        check_stack(unrolled::List::<_, 4>::new());
+    }
+
+    #[test]
+    fn queues() {
+        check_queue(fourth::List::new());
+        check_queue(fourth_slab::List::new());
+        check_queue(fifth::List::new());
+        check_queue(fifth_noref::List::new());
+        check_queue(fifth_sync::List::new());
+        check_queue(sixth::List::new());
+        check_queue(unrolled::List::<_, 4>::new());
+    }
+
+    #[test]
+    fn deques() {
+        check_deque(fourth::List::new());
+        check_deque(fourth_slab::List::new());
+        check_deque(sixth::List::new());
+        check_deque(unrolled::List::<_, 4>::new());
+    }
+
+    #[test]
+    fn persistent_stacks() {
+        check_persistent_stack::<third::List<i32>>();
+        check_persistent_stack::<third_sync::List<i32>>();
+        check_persistent_stack::<third_skew::List<i32>>();
+    }
+}
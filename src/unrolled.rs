@@ -0,0 +1,619 @@
+/*
+ * Unrolled linked list: a linked list of small arrays.
+ *
+ * Walking `second` or `fifth` means one pointer hop (and likely one cache
+ * miss) per element. Here every node ("chunk") holds up to `N` elements
+ * side by side, so iteration hops once per chunk and otherwise just walks
+ * an array, and there are N times fewer nodes to allocate.
+ *
+ * The chunks live in a `sixth::List`, so both ends are O(1) and the middle
+ * is edited through its cursor. Indexing walks the chunks: O(n / N).
+ *
+ * Invariants:
+ * - no chunk is empty,
+ * - `len` is the sum of the chunk lengths.
+ * Inserting into a full chunk splits it in two halves; removing from a chunk
+ * that drops below half full merges it with its successor if they fit in
+ * one. (The end chunks aren't kept half full: pushes and pops there never
+ * split or merge.)
+ */
+
+use std::fmt;
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+use std::slice;
+
+use crate::sixth;
+
+pub struct List<T, const N: usize = 16> {
+    chunks: sixth::List<Chunk<T, N>>,
+    len: usize,
+}
+
+// Up to `N` elements, initialized exactly in `elems[..len]`.
+struct Chunk<T, const N: usize> {
+    len: usize,
+    elems: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> Chunk<T, N> {
+    fn new() -> Self {
+        Chunk { len: 0, elems: [const { MaybeUninit::uninit() }; N] }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.elems.as_ptr() as *const T, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.elems.as_mut_ptr() as *mut T, self.len) }
+    }
+
+    // Shifts `[i..]` one to the right. Must not be full.
+    fn insert(&mut self, i: usize, elem: T) {
+        assert!(i <= self.len && self.len < N);
+        unsafe {
+            let at = self.elems.as_mut_ptr().add(i);
+            ptr::copy(at, at.add(1), self.len - i);
+            at.write(MaybeUninit::new(elem));
+        }
+        self.len += 1;
+    }
+
+    // Shifts `[i + 1..]` one to the left.
+    fn remove(&mut self, i: usize) -> T {
+        assert!(i < self.len);
+        self.len -= 1;
+        unsafe {
+            let at = self.elems.as_mut_ptr().add(i);
+            let elem = at.read().assume_init();
+            ptr::copy(at.add(1), at, self.len - i);
+            elem
+        }
+    }
+
+    // Moves `[at..]` into a new chunk.
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len);
+        let mut other = Chunk::new();
+        other.len = self.len - at;
+        self.len = at;
+        unsafe {
+            ptr::copy_nonoverlapping(self.elems.as_ptr().add(at), other.elems.as_mut_ptr(), other.len);
+        }
+        other
+    }
+
+    // Moves all of `other` to our back. Must fit.
+    fn append(&mut self, mut other: Self) {
+        assert!(self.len + other.len <= N);
+        unsafe {
+            ptr::copy_nonoverlapping(other.elems.as_ptr(), self.elems.as_mut_ptr().add(self.len), other.len);
+        }
+        // the elements are ours now: `other` mustn't drop them
+        self.len += mem::replace(&mut other.len, 0);
+    }
+}
+
+impl<T, const N: usize> Drop for Chunk<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T, const N: usize> List<T, N> {
+    pub fn new() -> Self {
+        // a full chunk has to split into two non-empty halves
+        const { assert!(N >= 2, "chunks must hold at least 2 elements") };
+        List { chunks: sixth::List::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        if self.chunks.front().map_or(true, Chunk::is_full) {
+            self.chunks.push_front(Chunk::new());
+        }
+        self.chunks.front_mut().unwrap().insert(0, elem);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        if self.chunks.back().map_or(true, Chunk::is_full) {
+            self.chunks.push_back(Chunk::new());
+        }
+        let chunk = self.chunks.back_mut().unwrap();
+        chunk.insert(chunk.len, elem);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let chunk = self.chunks.front_mut()?;
+        let elem = chunk.remove(0);
+        if chunk.len == 0 {
+            self.chunks.pop_front();
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let chunk = self.chunks.back_mut()?;
+        let elem = chunk.remove(chunk.len - 1);
+        if chunk.len == 0 {
+            self.chunks.pop_back();
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.chunks.front().map(|chunk| &chunk.as_slice()[0])
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.chunks.back().map(|chunk| &chunk.as_slice()[chunk.len - 1])
+    }
+
+    pub fn get(&self, mut i: usize) -> Option<&T> {
+        for chunk in &self.chunks {
+            if i < chunk.len {
+                return Some(&chunk.as_slice()[i]);
+            }
+            i -= chunk.len;
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, mut i: usize) -> Option<&mut T> {
+        for chunk in &mut self.chunks {
+            if i < chunk.len {
+                return Some(&mut chunk.as_mut_slice()[i]);
+            }
+            i -= chunk.len;
+        }
+        None
+    }
+
+    // Shifts everything after `i` back by one. A full chunk is split first.
+    //
+    // Panics if `i > len`.
+    pub fn insert(&mut self, i: usize, elem: T) {
+        assert!(i <= self.len, "insertion index (is {}) should be <= len (is {})", i, self.len);
+        if i == self.len {
+            return self.push_back(elem);
+        }
+        let (mut cursor, at) = self.cursor_at(i);
+        let chunk = cursor.current().unwrap();
+        if chunk.is_full() {
+            let mut back = chunk.split_off(N / 2);
+            if at <= N / 2 {
+                chunk.insert(at, elem);
+            } else {
+                back.insert(at - N / 2, elem);
+            }
+            cursor.insert_after(back);
+        } else {
+            chunk.insert(at, elem);
+        }
+        self.len += 1;
+    }
+
+    // Like `VecDeque::remove`: None if `i` is out of bounds. A chunk left
+    // less than half full is merged with the next one if they fit together.
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+        let (mut cursor, at) = self.cursor_at(i);
+        let chunk = cursor.current().unwrap();
+        let elem = chunk.remove(at);
+        let remaining = chunk.len;
+        if remaining == 0 {
+            cursor.remove_current();
+        } else if remaining < N / 2 && cursor.peek_next().is_some_and(|next| remaining + next.len <= N) {
+            cursor.move_next();
+            let next = cursor.remove_current().unwrap();
+            cursor.move_prev();
+            cursor.current().unwrap().append(next);
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    // A cursor on the chunk holding element `i` (< len) and `i`'s position
+    // in it. Walks in from whichever end is closer.
+    fn cursor_at(&mut self, i: usize) -> (sixth::CursorMut<'_, Chunk<T, N>>, usize) {
+        if i < self.len / 2 {
+            let mut i = i;
+            let mut cursor = self.chunks.cursor_front_mut();
+            loop {
+                let len = cursor.current().unwrap().len;
+                if i < len {
+                    return (cursor, i);
+                }
+                i -= len;
+                cursor.move_next();
+            }
+        } else {
+            let mut from_back = self.len - 1 - i;
+            let mut cursor = self.chunks.cursor_back_mut();
+            loop {
+                let len = cursor.current().unwrap().len;
+                if from_back < len {
+                    return (cursor, len - 1 - from_back);
+                }
+                from_back -= len;
+                cursor.move_prev();
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for List<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for List<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        for elem in iter {
+            list.push_back(elem);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for List<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+/*
+ * Iterators: a slice iterator over the current chunk at each end, refilled
+ * from the chunk list's own iterator when it runs dry. Once the chunk
+ * iterator runs out, the only elements left are in the other end's slice,
+ * so we drain that one from our side. `len` is only kept for `size_hint`.
+ */
+pub struct Iter<'a, T, const N: usize> {
+    chunks: sixth::Iter<'a, Chunk<T, N>>,
+    front: slice::Iter<'a, T>,
+    back: slice::Iter<'a, T>,
+    len: usize,
+}
+
+pub struct IterMut<'a, T, const N: usize> {
+    chunks: sixth::IterMut<'a, Chunk<T, N>>,
+    front: slice::IterMut<'a, T>,
+    back: slice::IterMut<'a, T>,
+    len: usize,
+}
+
+pub struct IntoIter<T, const N: usize>(List<T, N>);
+
+impl<T, const N: usize> List<T, N> {
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter { chunks: self.chunks.iter(), front: [].iter(), back: [].iter(), len: self.len }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        IterMut { chunks: self.chunks.iter_mut(), front: [].iter_mut(), back: [].iter_mut(), len: self.len }
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.front.next() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.as_slice().iter(),
+                None => {
+                    let elem = self.back.next()?;
+                    self.len -= 1;
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.back.next_back() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.as_slice().iter(),
+                None => {
+                    let elem = self.front.next_back()?;
+                    self.len -= 1;
+                    return Some(elem);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {}
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.front.next() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.as_mut_slice().iter_mut(),
+                None => {
+                    let elem = self.back.next()?;
+                    self.len -= 1;
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IterMut<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.back.next_back() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.as_mut_slice().iter_mut(),
+                None => {
+                    let elem = self.front.next_back()?;
+                    self.len -= 1;
+                    return Some(elem);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IterMut<'_, T, N> {}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> IntoIterator for List<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a List<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+    fn into_iter(self) -> Iter<'a, T, N> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut List<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, N>;
+    fn into_iter(self) -> IterMut<'a, T, N> {
+        self.iter_mut()
+    }
+}
+
+// $ cargo +nightly miri test unrolled
+#[cfg(test)]
+mod test {
+    use super::List;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    fn chunk_lens<T, const N: usize>(list: &List<T, N>) -> Vec<usize> {
+        list.chunks.iter().map(|chunk| chunk.len).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut list: List<i32, 4> = List::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.front(), None);
+        for i in 0..10 {
+            list.push_back(i);
+        }
+        list.push_front(-1);
+        assert_eq!(list.len(), 11);
+        assert_eq!(chunk_lens(&list), vec![1, 4, 4, 2]);
+        assert_eq!(list.front(), Some(&-1));
+        assert_eq!(list.back(), Some(&9));
+        assert_eq!(list.get(5), Some(&4));
+        assert_eq!(list.get(11), None);
+        *list.get_mut(0).unwrap() = 100;
+
+        assert_eq!(list.pop_front(), Some(100));
+        assert_eq!(chunk_lens(&list), vec![4, 4, 2]);
+        assert_eq!(list.pop_back(), Some(9));
+        assert_eq!(list.pop_back(), Some(8));
+        assert_eq!(chunk_lens(&list), vec![4, 4]);
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn split_and_merge() {
+        let mut list: List<i32, 4> = (0..8).collect();
+        assert_eq!(chunk_lens(&list), vec![4, 4]);
+
+        // inserting into a full chunk splits it
+        list.insert(1, 10);
+        assert_eq!(chunk_lens(&list), vec![3, 2, 4]);
+        list.insert(7, 70);
+        assert_eq!(chunk_lens(&list), vec![3, 2, 3, 2]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 10, 1, 2, 3, 4, 5, 70, 6, 7]);
+
+        // dropping below half full merges with the next chunk...
+        assert_eq!(list.remove(3), Some(2));
+        assert_eq!(chunk_lens(&list), vec![3, 4, 2]);
+        // ...but only if they fit into one
+        assert_eq!(list.remove(0), Some(0));
+        assert_eq!(chunk_lens(&list), vec![2, 4, 2]);
+        assert_eq!(list.remove(0), Some(10));
+        assert_eq!(chunk_lens(&list), vec![1, 4, 2]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4, 5, 70, 6, 7]);
+
+        // emptied chunks go away
+        let mut list: List<i32, 2> = (0..4).collect();
+        assert_eq!(list.remove(2), Some(2));
+        assert_eq!(list.remove(2), Some(3));
+        assert_eq!(chunk_lens(&list), vec![2]);
+        assert_eq!(list.remove(2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 3)")]
+    fn insert_out_of_bounds() {
+        let mut list: List<i32> = (0..3).collect();
+        list.insert(4, 0);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list: List<i32, 3> = (0..10).collect();
+        assert!(list.iter().copied().eq(0..10));
+        assert!(list.iter().rev().copied().eq((0..10).rev()));
+
+        // both ends meet in the middle, even inside one chunk
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.by_ref().rev().take(7).count(), 7);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), None);
+
+        for x in &mut list {
+            *x *= 10;
+        }
+        let mut iter = list.iter_mut();
+        *iter.next_back().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        assert_eq!(iter.count(), 8);
+        assert_eq!(format!("{:?}", list), "[2, 10, 20, 30, 40, 50, 60, 70, 80, 91]");
+
+        let mut into = list.into_iter();
+        assert_eq!(into.next_back(), Some(91));
+        assert_eq!(into.len(), 9);
+        assert!(into.eq([2, 10, 20, 30, 40, 50, 60, 70, 80]));
+    }
+
+    #[test]
+    fn against_model() {
+        let mut list: List<usize, 5> = List::new();
+        let mut model = VecDeque::new();
+        let mut seed = 17u32;
+        let mut rand = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        for i in 0..5000 {
+            match rand(6) {
+                0 => {
+                    list.push_front(i);
+                    model.push_front(i);
+                }
+                1 => {
+                    list.push_back(i);
+                    model.push_back(i);
+                }
+                2 => assert_eq!(list.pop_front(), model.pop_front()),
+                3 => assert_eq!(list.pop_back(), model.pop_back()),
+                4 => {
+                    let at = rand(model.len() + 1);
+                    list.insert(at, i);
+                    model.insert(at, i);
+                }
+                _ => {
+                    let at = rand(model.len() + 2);
+                    assert_eq!(list.remove(at), model.remove(at));
+                }
+            }
+            assert_eq!(list.len(), model.len());
+            assert!(list.chunks.iter().all(|chunk| chunk.len > 0));
+            if !model.is_empty() {
+                let at = rand(model.len());
+                assert_eq!(list.get(at), model.get(at));
+            }
+        }
+        assert!(list.iter().eq(model.iter()));
+        assert!(list.iter().rev().eq(model.iter().rev()));
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let shared = Rc::new(());
+        let mut list: List<Rc<()>, 4> = (0..20).map(|_| shared.clone()).collect();
+        list.insert(3, shared.clone());
+        for i in (0..10).step_by(2) {
+            list.remove(i);
+        }
+        drop(list.pop_front());
+        assert_eq!(Rc::strong_count(&shared), 1 + 15);
+        let mut into = list.into_iter();
+        into.next();
+        drop(into);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+}
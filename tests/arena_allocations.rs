@@ -0,0 +1,77 @@
+// `second_arena` against `second`, counting heap allocations.
+//
+// This is its own test binary because the counting allocator below replaces
+// the global allocator for the whole binary it's compiled into.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use lists::{second, second_arena};
+
+// Counts allocations per thread (the harness runs tests on their own
+// threads, and allocates on others).
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn steady_state_allocations() {
+    // second::List: one allocation per push, every time
+    let mut boxed = second::List::new();
+    let before = allocations();
+    for i in 0..1000 {
+        boxed.push(i);
+    }
+    assert_eq!(allocations() - before, 1000);
+
+    // the arena: nothing at all with enough capacity...
+    let mut list = second_arena::List::with_capacity(1000);
+    let before = allocations();
+    for _ in 0..10 {
+        for i in 0..1000 {
+            list.push(i);
+        }
+        while list.pop().is_some() {}
+    }
+    assert_eq!(allocations(), before);
+
+    // ...and nothing after the first round without
+    let mut list = second_arena::List::new();
+    for i in 0..1000 {
+        list.push(i);
+    }
+    while list.pop().is_some() {}
+    let before = allocations();
+    for _ in 0..10 {
+        for i in 0..1000 {
+            list.push(i);
+        }
+        for _ in 0..500 {
+            list.pop();
+        }
+        for i in 0..500 {
+            list.push(i);
+        }
+        while list.pop().is_some() {}
+    }
+    assert_eq!(allocations(), before);
+}